@@ -0,0 +1,344 @@
+#![allow(dead_code)]
+
+use std::{
+    cmp::Ordering,
+    fmt,
+    iter::FromIterator,
+    mem,
+    ops::{Deref, DerefMut},
+    slice,
+};
+
+use crate::vec::Vec;
+
+// Decides which item ends up on top: the one that compares greatest.
+pub trait Compare<T> {
+    fn compare(&self, a: &T, b: &T) -> Ordering;
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MaxFirst;
+
+impl<T: Ord> Compare<T> for MaxFirst {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        a.cmp(b)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MinFirst;
+
+impl<T: Ord> Compare<T> for MinFirst {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        b.cmp(a)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct FnCompare<F>(pub F);
+
+impl<T, F: Fn(&T, &T) -> Ordering> Compare<T> for FnCompare<F> {
+    fn compare(&self, a: &T, b: &T) -> Ordering {
+        (self.0)(a, b)
+    }
+}
+
+#[derive(Clone)]
+pub struct BinaryHeap<T, C = MaxFirst> {
+    data: Vec<T>,
+    cmp: C,
+}
+
+impl<T: Ord> BinaryHeap<T> {
+    pub fn new() -> BinaryHeap<T> {
+        BinaryHeap::with_comparator(MaxFirst)
+    }
+}
+
+impl<T: Ord> BinaryHeap<T, MinFirst> {
+    pub fn new_min() -> BinaryHeap<T, MinFirst> {
+        BinaryHeap::with_comparator(MinFirst)
+    }
+}
+
+impl<T, C: Compare<T>> BinaryHeap<T, C> {
+    pub fn with_comparator(cmp: C) -> BinaryHeap<T, C> {
+        BinaryHeap {
+            data: Vec::new(),
+            cmp,
+        }
+    }
+
+    // takes over `data` as storage and heapifies it bottom-up in O(n)
+    pub fn from_vec_with(data: Vec<T>, cmp: C) -> BinaryHeap<T, C> {
+        let mut heap = BinaryHeap { data, cmp };
+        for node in (0..heap.len() / 2).rev() {
+            heap.sift_down(node, heap.len());
+        }
+        heap
+    }
+
+    pub fn len(&self) -> usize {
+        self.data.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.data.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.data.capacity()
+    }
+
+    pub fn push(&mut self, item: T) {
+        self.data.push(item);
+        self.sift_up(self.len() - 1);
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let last = self.data.pop()?;
+        if self.is_empty() {
+            return Some(last);
+        }
+
+        let top = mem::replace(&mut self.data[0], last);
+        self.sift_down(0, self.len());
+        Some(top)
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.data.first()
+    }
+
+    // the top item may be changed in place, it is moved to its proper
+    // position once the returned guard is dropped
+    pub fn peek_mut(&mut self) -> Option<PeekMut<'_, T, C>> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(PeekMut {
+                heap: self,
+                sift: true,
+            })
+        }
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.data.iter()
+    }
+
+    pub fn clear(&mut self) {
+        while self.data.pop().is_some() {}
+    }
+
+    pub fn into_vec(self) -> Vec<T> {
+        self.data
+    }
+
+    // ascending according to the comparator, i.e. the top item comes last
+    pub fn into_sorted_vec(mut self) -> Vec<T> {
+        for end in (1..self.len()).rev() {
+            self.data.swap(0, end);
+            self.sift_down(0, end);
+        }
+        self.data
+    }
+
+    fn sift_up(&mut self, mut node: usize) {
+        while node > 0 {
+            let parent = (node - 1) / 2;
+            if self.cmp.compare(&self.data[node], &self.data[parent]) != Ordering::Greater {
+                break;
+            }
+            self.data.swap(node, parent);
+            node = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut node: usize, end: usize) {
+        loop {
+            let mut child = 2 * node + 1;
+            if child >= end {
+                break;
+            }
+            if child + 1 < end
+                && self.cmp.compare(&self.data[child + 1], &self.data[child]) == Ordering::Greater
+            {
+                child += 1;
+            }
+            if self.cmp.compare(&self.data[child], &self.data[node]) != Ordering::Greater {
+                break;
+            }
+            self.data.swap(node, child);
+            node = child;
+        }
+    }
+}
+
+impl<T: Ord> Default for BinaryHeap<T> {
+    fn default() -> BinaryHeap<T> {
+        BinaryHeap::new()
+    }
+}
+
+impl<T: fmt::Debug, C> fmt::Debug for BinaryHeap<T, C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.data.iter()).finish()
+    }
+}
+
+impl<T: Ord> From<Vec<T>> for BinaryHeap<T> {
+    fn from(data: Vec<T>) -> BinaryHeap<T> {
+        BinaryHeap::from_vec_with(data, MaxFirst)
+    }
+}
+
+impl<T: Ord> FromIterator<T> for BinaryHeap<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> BinaryHeap<T> {
+        BinaryHeap::from(iter.into_iter().collect::<Vec<T>>())
+    }
+}
+
+impl<T, C: Compare<T>> Extend<T> for BinaryHeap<T, C> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        self.data.reserve_additional(lower);
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+pub struct PeekMut<'a, T, C: Compare<T>> {
+    heap: &'a mut BinaryHeap<T, C>,
+    sift: bool,
+}
+
+impl<T, C: Compare<T>> PeekMut<'_, T, C> {
+    pub fn pop(mut this: PeekMut<'_, T, C>) -> T {
+        this.sift = false;
+        this.heap.pop().unwrap()
+    }
+}
+
+impl<T, C: Compare<T>> Deref for PeekMut<'_, T, C> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.heap.data[0]
+    }
+}
+
+impl<T, C: Compare<T>> DerefMut for PeekMut<'_, T, C> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.heap.data[0]
+    }
+}
+
+impl<T, C: Compare<T>> Drop for PeekMut<'_, T, C> {
+    fn drop(&mut self) {
+        if self.sift {
+            let len = self.heap.len();
+            self.heap.sift_down(0, len);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BinaryHeap, FnCompare, PeekMut};
+    use crate::vec::Vec;
+
+    #[test]
+    fn push_pop() {
+        let mut heap = BinaryHeap::new();
+        [5, 1, 8, 3, 9, 2, 8].iter().for_each(|&n| heap.push(n));
+        assert_eq!(heap.len(), 7);
+        assert_eq!(heap.peek(), Some(&9));
+
+        let mut popped = Vec::new();
+        while let Some(n) = heap.pop() {
+            popped.push(n);
+        }
+        assert_eq!(popped, [9, 8, 8, 5, 3, 2, 1]);
+        assert!(heap.is_empty());
+    }
+
+    #[test]
+    fn min_heap() {
+        let mut heap = BinaryHeap::new_min();
+        heap.extend([5, 1, 8, 3]);
+        assert_eq!(heap.pop(), Some(1));
+        assert_eq!(heap.pop(), Some(3));
+        assert_eq!(heap.into_sorted_vec(), [8, 5]);
+
+        // small extends grow the storage by doubling, not one slot at a time
+        let mut heap = BinaryHeap::new();
+        for n in 0..100 {
+            heap.extend([n]);
+            assert!(heap.capacity().is_power_of_two());
+        }
+        assert_eq!(heap.peek(), Some(&99));
+    }
+
+    #[test]
+    fn heapify() {
+        let data: Vec<u32> = (0..100).map(|n| (n * 37) % 101).collect();
+        let storage = data.as_ptr();
+        let heap = BinaryHeap::from(data);
+        assert_eq!(heap.into_vec().as_ptr(), storage);
+
+        let data: Vec<u32> = (0..100).map(|n| (n * 37) % 101).collect();
+        let mut expected = data.clone();
+        expected.sort();
+        assert_eq!(BinaryHeap::from(data).into_sorted_vec(), expected);
+    }
+
+    #[test]
+    fn peek_mut_sifts_on_drop() {
+        let mut heap: BinaryHeap<i32> = [3, 7, 5].into_iter().collect();
+        *heap.peek_mut().unwrap() = 1;
+        assert_eq!(heap.peek(), Some(&5));
+
+        if let Some(mut top) = heap.peek_mut() {
+            *top += 10;
+        }
+        assert_eq!(heap.peek(), Some(&15));
+
+        let top = heap.peek_mut().unwrap();
+        assert_eq!(PeekMut::pop(top), 15);
+        assert_eq!(heap.into_sorted_vec(), [1, 3]);
+    }
+
+    #[test]
+    fn scheduler() {
+        #[derive(Debug, PartialEq)]
+        struct Task {
+            deadline: u32,
+            name: &'static str,
+        }
+
+        let earliest_first = FnCompare(|a: &Task, b: &Task| b.deadline.cmp(&a.deadline));
+        let mut queue = BinaryHeap::with_comparator(earliest_first);
+        queue.push(Task {
+            deadline: 30,
+            name: "backup",
+        });
+        queue.push(Task {
+            deadline: 10,
+            name: "render",
+        });
+        queue.push(Task {
+            deadline: 20,
+            name: "flush",
+        });
+
+        let order: Vec<&str> = std::iter::from_fn(|| queue.pop().map(|task| task.name)).collect();
+        assert_eq!(order, ["render", "flush", "backup"]);
+
+        let by_len = FnCompare(|a: &&str, b: &&str| a.len().cmp(&b.len()));
+        let heap = BinaryHeap::from_vec_with(Vec::from(["aaa", "a", "aa"]), by_len);
+        assert_eq!(heap.into_sorted_vec(), ["a", "aa", "aaa"]);
+    }
+}
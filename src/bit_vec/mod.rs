@@ -0,0 +1,445 @@
+#![allow(dead_code)]
+
+use std::{
+    fmt,
+    iter::FromIterator,
+    ops::{BitAndAssign, BitOrAssign, BitXorAssign, Not},
+};
+
+use crate::vec::Vec;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+// Bits beyond `len` in the last word are always kept at zero so whole words
+// can be compared and counted without masking.
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct BitVec {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl BitVec {
+    pub fn new() -> BitVec {
+        BitVec {
+            words: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(bits: usize) -> BitVec {
+        let mut words = Vec::new();
+        words.reserve(words_for(bits));
+        BitVec { words, len: 0 }
+    }
+
+    pub fn from_elem(len: usize, bit: bool) -> BitVec {
+        let fill = if bit { u64::MAX } else { 0 };
+        let mut bits = BitVec {
+            words: std::iter::repeat_n(fill, words_for(len)).collect(),
+            len,
+        };
+        bits.clear_unused_bits();
+        bits
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.words.capacity().saturating_mul(WORD_BITS)
+    }
+
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    pub fn push(&mut self, bit: bool) {
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.push(0);
+        }
+        self.len += 1;
+        self.set(self.len - 1, bit);
+    }
+
+    pub fn pop(&mut self) -> Option<bool> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let bit = self.get(self.len - 1);
+        self.set(self.len - 1, false);
+        self.len -= 1;
+        if self.len.is_multiple_of(WORD_BITS) {
+            self.words.pop();
+        }
+        bit
+    }
+
+    pub fn get(&self, index: usize) -> Option<bool> {
+        if index < self.len {
+            let (word, mask) = locate(index);
+            Some(self.words[word] & mask != 0)
+        } else {
+            None
+        }
+    }
+
+    pub fn set(&mut self, index: usize, bit: bool) {
+        assert!(index < self.len, "index out of bounds");
+
+        let (word, mask) = locate(index);
+        if bit {
+            self.words[word] |= mask;
+        } else {
+            self.words[word] &= !mask;
+        }
+    }
+
+    pub fn count_ones(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn count_zeros(&self) -> usize {
+        self.len - self.count_ones()
+    }
+
+    // number of set bits in `0..index`
+    pub fn rank(&self, index: usize) -> usize {
+        assert!(index <= self.len, "index out of bounds");
+
+        let (word, _) = locate(index);
+        let full: usize = self.words[..word]
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum();
+        let partial = match index % WORD_BITS {
+            0 => 0,
+            bits => (self.words[word] << (WORD_BITS - bits)).count_ones() as usize,
+        };
+        full + partial
+    }
+
+    // position of the set bit with rank `n`, i.e. the (n + 1)th set bit
+    pub fn select(&self, mut n: usize) -> Option<usize> {
+        for (i, &word) in self.words.iter().enumerate() {
+            let ones = word.count_ones() as usize;
+            if n < ones {
+                return Some(i * WORD_BITS + select_in_word(word, n));
+            }
+            n -= ones;
+        }
+        None
+    }
+
+    pub fn and(&mut self, other: &BitVec) {
+        self.zip_words(other, |a, b| a & b);
+    }
+
+    pub fn or(&mut self, other: &BitVec) {
+        self.zip_words(other, |a, b| a | b);
+    }
+
+    pub fn xor(&mut self, other: &BitVec) {
+        self.zip_words(other, |a, b| a ^ b);
+    }
+
+    pub fn not(&mut self) {
+        for word in self.words.iter_mut() {
+            *word = !*word;
+        }
+        self.clear_unused_bits();
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            bits: self,
+            front: 0,
+            back: self.len,
+        }
+    }
+
+    pub fn iter_ones(&self) -> IterOnes<'_> {
+        IterOnes {
+            words: &self.words,
+            index: 0,
+            current: self.words.first().copied().unwrap_or(0),
+        }
+    }
+
+    fn zip_words(&mut self, other: &BitVec, op: impl Fn(u64, u64) -> u64) {
+        assert_eq!(self.len, other.len, "bit vectors differ in length");
+
+        for (word, other) in self.words.iter_mut().zip(other.words.iter()) {
+            *word = op(*word, *other);
+        }
+    }
+
+    fn clear_unused_bits(&mut self) {
+        let used = self.len % WORD_BITS;
+        if used != 0 {
+            if let Some(last) = self.words.last_mut() {
+                *last &= (1 << used) - 1;
+            }
+        }
+    }
+}
+
+fn words_for(bits: usize) -> usize {
+    bits.div_ceil(WORD_BITS)
+}
+
+fn locate(index: usize) -> (usize, u64) {
+    (index / WORD_BITS, 1 << (index % WORD_BITS))
+}
+
+fn select_in_word(mut word: u64, n: usize) -> usize {
+    for _ in 0..n {
+        word &= word - 1;
+    }
+    word.trailing_zeros() as usize
+}
+
+impl fmt::Debug for BitVec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for bit in self.iter() {
+            f.write_str(if bit { "1" } else { "0" })?;
+        }
+        Ok(())
+    }
+}
+
+impl FromIterator<bool> for BitVec {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> BitVec {
+        let mut bits = BitVec::new();
+        bits.extend(iter);
+        bits
+    }
+}
+
+impl Extend<bool> for BitVec {
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        let needed = self.len.checked_add(lower).expect("capacity overflow");
+        self.words
+            .reserve_additional(words_for(needed) - self.words.len());
+        for bit in iter {
+            self.push(bit);
+        }
+    }
+}
+
+impl BitAndAssign<&BitVec> for BitVec {
+    fn bitand_assign(&mut self, other: &BitVec) {
+        self.and(other);
+    }
+}
+
+impl BitOrAssign<&BitVec> for BitVec {
+    fn bitor_assign(&mut self, other: &BitVec) {
+        self.or(other);
+    }
+}
+
+impl BitXorAssign<&BitVec> for BitVec {
+    fn bitxor_assign(&mut self, other: &BitVec) {
+        self.xor(other);
+    }
+}
+
+impl Not for BitVec {
+    type Output = BitVec;
+
+    fn not(mut self) -> BitVec {
+        BitVec::not(&mut self);
+        self
+    }
+}
+
+pub struct Iter<'a> {
+    bits: &'a BitVec,
+    front: usize,
+    back: usize,
+}
+
+impl Iterator for Iter<'_> {
+    type Item = bool;
+
+    fn next(&mut self) -> Option<bool> {
+        if self.front == self.back {
+            None
+        } else {
+            self.front += 1;
+            self.bits.get(self.front - 1)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.back - self.front;
+        (len, Some(len))
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<bool> {
+        if self.front == self.back {
+            None
+        } else {
+            self.back -= 1;
+            self.bits.get(self.back)
+        }
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+impl<'a> IntoIterator for &'a BitVec {
+    type Item = bool;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+// Yields the positions of set bits, skipping whole zero words at a time.
+pub struct IterOnes<'a> {
+    words: &'a [u64],
+    index: usize,
+    current: u64,
+}
+
+impl Iterator for IterOnes<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.current == 0 {
+            self.index += 1;
+            self.current = *self.words.get(self.index)?;
+        }
+
+        let bit = self.current.trailing_zeros() as usize;
+        self.current &= self.current - 1;
+        Some(self.index * WORD_BITS + bit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BitVec;
+
+    fn pattern(len: usize) -> BitVec {
+        (0..len).map(|n| n % 3 == 0 || n % 7 == 0).collect()
+    }
+
+    #[test]
+    fn push_pop_get_set() {
+        let mut bits = BitVec::new();
+        (0..130).for_each(|n| bits.push(n % 2 == 0));
+        assert_eq!(bits.len(), 130);
+        assert_eq!(bits.as_words().len(), 3);
+        assert_eq!(bits.get(64), Some(true));
+        assert_eq!(bits.get(129), Some(false));
+        assert_eq!(bits.get(130), None);
+
+        bits.set(129, true);
+        assert_eq!(bits.pop(), Some(true));
+        assert_eq!(bits.pop(), Some(true));
+        assert_eq!(bits.as_words().len(), 2);
+        while bits.pop().is_some() {}
+        assert!(bits.is_empty());
+        assert_eq!(bits.as_words().len(), 0);
+    }
+
+    #[test]
+    fn count() {
+        let bits = pattern(200);
+        let expected = (0..200).filter(|n| n % 3 == 0 || n % 7 == 0).count();
+        assert_eq!(bits.count_ones(), expected);
+        assert_eq!(bits.count_zeros(), 200 - expected);
+        assert_eq!(BitVec::from_elem(70, true).count_ones(), 70);
+        assert_eq!(BitVec::from_elem(70, false).count_ones(), 0);
+    }
+
+    #[test]
+    fn rank_select() {
+        let bits = pattern(300);
+        let ones: Vec<usize> = (0..300).filter(|n| n % 3 == 0 || n % 7 == 0).collect();
+
+        for index in 0..=300 {
+            assert_eq!(
+                bits.rank(index),
+                ones.iter().filter(|&&n| n < index).count()
+            );
+        }
+        for (rank, &position) in ones.iter().enumerate() {
+            assert_eq!(bits.select(rank), Some(position));
+            assert_eq!(bits.rank(position), rank);
+        }
+        assert_eq!(bits.select(ones.len()), None);
+    }
+
+    #[test]
+    fn bulk_ops() {
+        let a: BitVec = (0..100).map(|n| n % 2 == 0).collect();
+        let b: BitVec = (0..100).map(|n| n % 3 == 0).collect();
+
+        let mut and = a.clone();
+        and &= &b;
+        assert!(and.iter().eq((0..100).map(|n| n % 6 == 0)));
+
+        let mut or = a.clone();
+        or |= &b;
+        assert!(or.iter().eq((0..100).map(|n| n % 2 == 0 || n % 3 == 0)));
+
+        let mut xor = a.clone();
+        xor ^= &b;
+        assert!(xor
+            .iter()
+            .eq((0..100).map(|n| (n % 2 == 0) != (n % 3 == 0))));
+
+        let not = !a;
+        assert!(not.iter().eq((0..100).map(|n| n % 2 != 0)));
+        assert_eq!(not.count_ones(), 50);
+    }
+
+    #[test]
+    fn iter_ones() {
+        let bits = pattern(500);
+        assert!(bits
+            .iter_ones()
+            .eq((0..500).filter(|n| n % 3 == 0 || n % 7 == 0)));
+
+        let mut sparse = BitVec::from_elem(1000, false);
+        sparse.set(3, true);
+        sparse.set(640, true);
+        sparse.set(999, true);
+        assert_eq!(sparse.iter_ones().collect::<Vec<_>>(), [3, 640, 999]);
+        assert_eq!(BitVec::new().iter_ones().next(), None);
+    }
+
+    #[test]
+    fn iter() {
+        let bits: BitVec = [true, false, true, true].into_iter().collect();
+        assert_eq!(format!("{:?}", bits), "1011");
+        assert!(bits.iter().rev().eq([true, true, false, true]));
+        assert_eq!(bits.iter().len(), 4);
+
+        // a word's worth of bits at a time still grows the words by doubling
+        let mut bits = BitVec::new();
+        for _ in 0..20 {
+            bits.extend([true; 64]);
+            assert!(bits.as_words().len() <= bits.capacity() / 64);
+            assert!((bits.capacity() / 64).is_power_of_two());
+        }
+        assert_eq!(bits.count_ones(), 20 * 64);
+    }
+}
@@ -157,7 +157,7 @@ impl<T: Clone> Iterator for Iter<T> {
     type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        while self.stack.len() != 0 || self.next.is_some() {
+        while !self.stack.is_empty() || self.next.is_some() {
             if self.next.is_some() {
                 self.stack.push(self.next);
                 self.next = unsafe { self.next.unwrap().as_ref().left };
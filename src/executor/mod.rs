@@ -0,0 +1,186 @@
+#![allow(dead_code)]
+
+use std::{
+    future::Future,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    thread::{self, Thread},
+};
+
+use crate::{
+    slot_map::{Key, SlotMap},
+    vec_deque::VecDeque,
+};
+
+type Task = Pin<Box<dyn Future<Output = ()>>>;
+
+// Runs futures on the calling thread. Tasks are polled only after their
+// waker fires, which may happen on any thread: it queues the task and
+// unparks the thread running the executor.
+pub struct Executor {
+    tasks: SlotMap<Task>,
+    ready: Arc<Mutex<VecDeque<Key>>>,
+}
+
+struct TaskWaker {
+    key: Key,
+    ready: Arc<Mutex<VecDeque<Key>>>,
+    thread: Thread,
+}
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.wake_by_ref();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.ready.lock().unwrap().push_back(self.key);
+        self.thread.unpark();
+    }
+}
+
+impl Executor {
+    pub fn new() -> Executor {
+        Executor {
+            tasks: SlotMap::new(),
+            ready: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    pub fn spawn<F: Future<Output = ()> + 'static>(&mut self, future: F) {
+        let key = self.tasks.insert(Box::pin(future));
+        self.ready.lock().unwrap().push_back(key);
+    }
+
+    // Polls tasks until all of them have finished, parking the thread while
+    // none is ready.
+    pub fn run(&mut self) {
+        while !self.tasks.is_empty() {
+            let next = self.ready.lock().unwrap().pop_front();
+            let key = match next {
+                Some(key) => key,
+                None => {
+                    thread::park();
+                    continue;
+                }
+            };
+
+            // a task woken twice may already have finished
+            let Some(task) = self.tasks.get_mut(key) else {
+                continue;
+            };
+            let waker = Waker::from(Arc::new(TaskWaker {
+                key,
+                ready: self.ready.clone(),
+                thread: thread::current(),
+            }));
+            if task
+                .as_mut()
+                .poll(&mut Context::from_waker(&waker))
+                .is_ready()
+            {
+                self.tasks.remove(key);
+            }
+        }
+    }
+}
+
+impl Default for Executor {
+    fn default() -> Executor {
+        Executor::new()
+    }
+}
+
+struct ThreadWaker(Thread);
+
+impl Wake for ThreadWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// Drives a single future to completion on the calling thread.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    let mut future = Box::pin(future);
+    let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+    let mut cx = Context::from_waker(&waker);
+    loop {
+        match future.as_mut().poll(&mut cx) {
+            Poll::Ready(output) => return output,
+            // spurious unparks just cost an extra poll
+            Poll::Pending => thread::park(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::{Cell, RefCell},
+        future::Future,
+        pin::Pin,
+        rc::Rc,
+        task::{Context, Poll},
+    };
+
+    use super::{block_on, Executor};
+    use crate::vec::Vec;
+
+    // pending once, waking itself right away
+    struct YieldNow(bool);
+
+    impl Future for YieldNow {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[test]
+    fn block_on_value() {
+        assert_eq!(block_on(async { 6 * 7 }), 42);
+        assert_eq!(
+            block_on(async {
+                YieldNow(false).await;
+                "done"
+            }),
+            "done"
+        );
+    }
+
+    #[test]
+    fn interleaves_tasks() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let finished = Rc::new(Cell::new(0));
+        let mut executor = Executor::new();
+        for task in 0..3 {
+            let log = log.clone();
+            let finished = finished.clone();
+            executor.spawn(async move {
+                for step in 0..2 {
+                    log.borrow_mut().push((task, step));
+                    YieldNow(false).await;
+                }
+                finished.set(finished.get() + 1);
+            });
+        }
+        executor.run();
+
+        assert_eq!(finished.get(), 3);
+        assert_eq!(
+            *log.borrow(),
+            [(0, 0), (1, 0), (2, 0), (0, 1), (1, 1), (2, 1)]
+        );
+    }
+}
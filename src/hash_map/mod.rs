@@ -0,0 +1,657 @@
+#![allow(dead_code)]
+
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::FromIterator,
+    mem,
+    ops::Index,
+};
+
+mod raw_table;
+
+pub use raw_table::RawTable;
+
+pub struct HashMap<K, V, S = RandomState> {
+    table: RawTable<(K, V)>,
+    hash_builder: S,
+}
+
+impl<K, V> HashMap<K, V> {
+    pub fn new() -> HashMap<K, V> {
+        HashMap::with_hasher(RandomState::new())
+    }
+
+    pub fn with_capacity(cap: usize) -> HashMap<K, V> {
+        HashMap::with_capacity_and_hasher(cap, RandomState::new())
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> HashMap<K, V, S> {
+        HashMap {
+            table: RawTable::new(),
+            hash_builder,
+        }
+    }
+
+    pub fn with_capacity_and_hasher(cap: usize, hash_builder: S) -> HashMap<K, V, S> {
+        HashMap {
+            table: RawTable::with_capacity(cap),
+            hash_builder,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.table.len() == 0
+    }
+
+    // how many entries fit before the table has to grow
+    pub fn capacity(&self) -> usize {
+        self.table.capacity()
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    // makes room for `additional` more entries, unlike `vec::Vec::reserve`
+    // which takes the total
+    pub fn reserve(&mut self, additional: usize) {
+        self.table.reserve(additional);
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        self.table.shrink_to_fit();
+    }
+
+    pub fn clear(&mut self) {
+        self.table.clear();
+    }
+
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        self.table.retain(|(key, value)| f(key, value));
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            inner: self.table.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            inner: self.table.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut V> {
+        self.iter_mut().map(|(_, value)| value)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> HashMap<K, V, S> {
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        let (key, value) = self.table.get(index);
+        Some((key, value))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        Some(&mut self.table.get_mut(index).1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.find(key)?;
+        Some(self.table.remove(index))
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.table.find(hash, |(other, _)| *other == key) {
+            Some(index) => Entry::Occupied(OccupiedEntry {
+                table: &mut self.table,
+                index,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                table: &mut self.table,
+                hash,
+                key,
+            }),
+        }
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_builder.hash_one(key);
+        self.table.find(hash, |(other, _)| other.borrow() == key)
+    }
+}
+
+pub enum Entry<'a, K, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+impl<'a, K, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, f: F) -> Entry<'a, K, V> {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+pub struct OccupiedEntry<'a, K, V> {
+    table: &'a mut RawTable<(K, V)>,
+    index: usize,
+}
+
+impl<'a, K, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.table.get(self.index).0
+    }
+
+    pub fn get(&self) -> &V {
+        &self.table.get(self.index).1
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.table.get_mut(self.index).1
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.table.get_mut(self.index).1
+    }
+
+    // replaces the value, keeping the key already in the map
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.remove_entry().1
+    }
+
+    pub fn remove_entry(self) -> (K, V) {
+        self.table.remove(self.index)
+    }
+}
+
+pub struct VacantEntry<'a, K, V> {
+    table: &'a mut RawTable<(K, V)>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let index = self.table.insert(self.hash, (self.key, value));
+        &mut self.table.get_mut(index).1
+    }
+}
+
+impl<K, V, S: Default> Default for HashMap<K, V, S> {
+    fn default() -> HashMap<K, V, S> {
+        HashMap::with_hasher(S::default())
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for HashMap<K, V, S> {
+    fn clone(&self) -> HashMap<K, V, S> {
+        HashMap {
+            table: self.table.clone(),
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for HashMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Hash + Eq, V: PartialEq, S: BuildHasher> PartialEq for HashMap<K, V, S> {
+    fn eq(&self, other: &HashMap<K, V, S>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K: Hash + Eq, V: Eq, S: BuildHasher> Eq for HashMap<K, V, S> {}
+
+impl<K, Q, V, S> Index<&Q> for HashMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for HashMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> HashMap<K, V, S> {
+        let mut map = HashMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for HashMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        self.reserve(lower);
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, const N: usize> From<[(K, V); N]> for HashMap<K, V> {
+    fn from(entries: [(K, V); N]) -> HashMap<K, V> {
+        entries.into_iter().collect()
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    inner: raw_table::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.inner.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+    inner: raw_table::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        self.inner.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+pub struct IntoIter<K, V> {
+    inner: raw_table::IntoIter<(K, V)>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V, S> IntoIterator for HashMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            inner: self.table.into_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a HashMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut HashMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        hash::{BuildHasherDefault, Hasher},
+        rc::Rc,
+    };
+
+    use super::{Entry, HashMap};
+    use crate::vec::Vec;
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    // sends every key into a handful of slots so probe sequences get long
+    #[derive(Default)]
+    struct Clumping(u64);
+
+    impl Hasher for Clumping {
+        fn finish(&self) -> u64 {
+            self.0 % 4
+        }
+
+        fn write(&mut self, bytes: &[u8]) {
+            for &byte in bytes {
+                self.0 = self.0.wrapping_mul(31).wrapping_add(byte as u64);
+            }
+        }
+    }
+
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    fn sorted<'a>(entries: impl Iterator<Item = (&'a u64, &'a u64)>) -> Vec<(u64, u64)> {
+        let mut entries: Vec<(u64, u64)> = entries.map(|(&k, &v)| (k, v)).collect();
+        entries.sort();
+        entries
+    }
+
+    fn differential<S: std::hash::BuildHasher>(mut map: HashMap<u64, u64, S>, seed: u64) {
+        let mut rng = Rng(seed);
+        let mut expected = std::collections::HashMap::new();
+
+        for step in 0..20_000 {
+            let key = rng.below(500);
+            match rng.below(8) {
+                0..=2 => assert_eq!(map.insert(key, step), expected.insert(key, step)),
+                3 | 4 => assert_eq!(map.remove(&key), expected.remove(&key)),
+                5 => {
+                    *map.entry(key).or_insert(0) += step;
+                    *expected.entry(key).or_insert(0) += step;
+                }
+                6 => assert_eq!(map.get(&key), expected.get(&key)),
+                _ => {
+                    map.retain(|k, v| (k + *v) % 7 != 0);
+                    expected.retain(|k, v| (k + *v) % 7 != 0);
+                }
+            }
+            assert_eq!(map.len(), expected.len());
+            assert!(map.len() <= map.capacity());
+        }
+
+        assert_eq!(sorted(map.iter()), sorted(expected.iter()));
+        for (key, value) in &expected {
+            assert_eq!(map[key], *value);
+        }
+    }
+
+    #[test]
+    fn against_std() {
+        differential(HashMap::new(), 1);
+        differential(
+            HashMap::with_hasher(BuildHasherDefault::<Clumping>::default()),
+            2,
+        );
+    }
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = HashMap::new();
+        assert_eq!(map.insert(String::from("one"), 1), None);
+        assert_eq!(map.insert(String::from("two"), 2), None);
+        assert_eq!(map.insert(String::from("one"), 10), Some(1));
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(map.get("one"), Some(&10));
+        assert_eq!(map.get_key_value("two"), Some((&String::from("two"), &2)));
+        assert!(map.contains_key("two"));
+        *map.get_mut("two").unwrap() += 1;
+        assert_eq!(map["two"], 3);
+
+        assert_eq!(map.remove_entry("one"), Some((String::from("one"), 10)));
+        assert_eq!(map.remove("one"), None);
+        assert_eq!(map.get("three"), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn entry_api() {
+        let text = "the quick fox jumps over the lazy dog the end";
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+        for word in text.split(' ') {
+            *counts.entry(word).or_default() += 1;
+        }
+        assert_eq!(counts["the"], 3);
+        assert_eq!(counts["fox"], 1);
+
+        counts.entry("fox").and_modify(|n| *n += 10).or_insert(0);
+        counts.entry("cat").and_modify(|n| *n += 10).or_insert(7);
+        assert_eq!(counts["fox"], 11);
+        assert_eq!(counts["cat"], 7);
+
+        match counts.entry("the") {
+            Entry::Occupied(mut entry) => {
+                assert_eq!(entry.key(), &"the");
+                assert_eq!(entry.insert(0), 3);
+                assert_eq!(entry.remove(), 0);
+            }
+            Entry::Vacant(_) => unreachable!(),
+        }
+        match counts.entry("the") {
+            Entry::Vacant(entry) => assert_eq!(entry.into_key(), "the"),
+            Entry::Occupied(_) => unreachable!(),
+        }
+        assert!(!counts.contains_key("the"));
+    }
+
+    #[test]
+    fn resizing() {
+        let mut map = HashMap::new();
+        assert_eq!(map.capacity(), 0);
+        for n in 0..1000 {
+            map.insert(n, n * n);
+            assert!(map.len() <= map.capacity());
+        }
+        assert_eq!(map.capacity(), 1792);
+        assert!((0..1000).all(|n| map[&n] == n * n));
+
+        map.retain(|&k, _| k < 10);
+        map.shrink_to_fit();
+        assert_eq!(map.capacity(), 14);
+        assert!((0..10).all(|n| map[&n] == n * n));
+
+        let mut reserved: HashMap<u32, u32> = HashMap::with_capacity(100);
+        let cap = reserved.capacity();
+        assert!(cap >= 100);
+        reserved.extend((0..100).map(|n| (n, n)));
+        assert_eq!(reserved.capacity(), cap);
+    }
+
+    #[test]
+    fn iteration() {
+        let mut map: HashMap<u64, u64> = (0..100).map(|n| (n, n)).collect();
+        for (_, value) in &mut map {
+            *value *= 2;
+        }
+        map.values_mut().for_each(|value| *value += 1);
+        assert_eq!(map.iter().len(), 100);
+        assert_eq!(
+            sorted(map.iter()),
+            (0..100).map(|n| (n, 2 * n + 1)).collect::<Vec<_>>()
+        );
+
+        let mut keys: Vec<u64> = map.keys().copied().collect();
+        keys.sort();
+        assert!(keys.iter().copied().eq(0..100));
+        assert_eq!(map.values().sum::<u64>(), 100 * 100);
+
+        let mut owned: Vec<(u64, u64)> = map.clone().into_iter().collect();
+        owned.sort();
+        assert_eq!(owned, sorted(map.iter()));
+        assert_eq!(map, map.clone());
+    }
+
+    #[test]
+    fn drops_entries() {
+        let drops = Rc::new(Cell::new(0));
+        let mut map = HashMap::new();
+        for n in 0..100 {
+            map.insert(n, DropCounter(drops.clone()));
+        }
+        map.insert(0, DropCounter(drops.clone()));
+        assert_eq!(drops.get(), 1);
+        map.remove(&1);
+        map.retain(|&k, _| k % 2 == 0);
+        assert_eq!(drops.get(), 51);
+        drop(map);
+        assert_eq!(drops.get(), 101);
+    }
+
+    #[test]
+    fn debug_and_eq() {
+        let map = HashMap::from([(1, "one")]);
+        assert_eq!(format!("{:?}", map), r#"{1: "one"}"#);
+
+        let a = HashMap::from([(1, 1), (2, 2), (3, 3)]);
+        let b: HashMap<i32, i32> = [(3, 3), (2, 2), (1, 1)].into_iter().collect();
+        assert_eq!(a, b);
+        assert_ne!(a, HashMap::from([(1, 1), (2, 2), (3, 4)]));
+    }
+}
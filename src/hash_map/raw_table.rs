@@ -0,0 +1,318 @@
+use std::{iter, mem, slice};
+
+use crate::vec::{self, Vec};
+
+const MIN_SLOTS: usize = 8;
+
+// the table grows once more than 7/8 of its slots would be occupied
+const MAX_LOAD_NUM: usize = 7;
+const MAX_LOAD_DEN: usize = 8;
+
+#[derive(Clone)]
+struct Bucket<T> {
+    hash: u64,
+    value: T,
+}
+
+// Robin Hood open addressing over a power of two number of slots. An item
+// that is further from its ideal slot than the one it probes past takes that
+// slot and the displaced item continues probing, which keeps probe sequences
+// short and sorted by distance. Lookups can then stop as soon as they meet
+// an item closer to home than the probe, and removals shift the following
+// items back instead of leaving tombstones.
+//
+// Hashes are stored next to the items so resizing never needs the hasher and
+// most mismatches are rejected without calling `eq`.
+#[derive(Clone)]
+pub struct RawTable<T> {
+    slots: Vec<Option<Bucket<T>>>,
+    len: usize,
+}
+
+impl<T> RawTable<T> {
+    pub fn new() -> RawTable<T> {
+        RawTable {
+            slots: Vec::new(),
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(cap: usize) -> RawTable<T> {
+        let mut table = RawTable::new();
+        table.reserve(cap);
+        table
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    // how many items fit before the next resize
+    pub fn capacity(&self) -> usize {
+        self.slots.len() / MAX_LOAD_DEN * MAX_LOAD_NUM
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        if needed > self.capacity() {
+            self.resize(slots_for(needed));
+        }
+    }
+
+    pub fn shrink_to_fit(&mut self) {
+        let slots = if self.len == 0 {
+            0
+        } else {
+            slots_for(self.len)
+        };
+        if slots < self.slots.len() {
+            self.resize(slots);
+        }
+    }
+
+    pub fn find<F: FnMut(&T) -> bool>(&self, hash: u64, mut eq: F) -> Option<usize> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let mut index = self.ideal_slot(hash);
+        let mut dist = 0;
+        while let Some(bucket) = &self.slots[index] {
+            if self.distance(bucket.hash, index) < dist {
+                break;
+            }
+            if bucket.hash == hash && eq(&bucket.value) {
+                return Some(index);
+            }
+            index = self.next_slot(index);
+            dist += 1;
+        }
+        None
+    }
+
+    // does not check for an equal item, callers look it up first;
+    // returns the slot the item ended up in
+    pub fn insert(&mut self, hash: u64, value: T) -> usize {
+        self.reserve(1);
+        self.insert_no_grow(Bucket { hash, value })
+    }
+
+    pub fn remove(&mut self, index: usize) -> T {
+        let bucket = self.slots[index].take().expect("empty slot");
+        self.len -= 1;
+
+        let mut hole = index;
+        loop {
+            let next = self.next_slot(hole);
+            match &self.slots[next] {
+                Some(moved) if self.distance(moved.hash, next) > 0 => {
+                    self.slots[hole] = self.slots[next].take();
+                    hole = next;
+                }
+                _ => break,
+            }
+        }
+        bucket.value
+    }
+
+    pub fn get(&self, index: usize) -> &T {
+        &self.slots[index].as_ref().expect("empty slot").value
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> &mut T {
+        &mut self.slots[index].as_mut().expect("empty slot").value
+    }
+
+    pub fn retain<F: FnMut(&mut T) -> bool>(&mut self, mut f: F) {
+        if self.len == 0 {
+            return;
+        }
+
+        // Removals only pull items back by one slot, and never past an empty
+        // slot, so walking once around the table from an empty slot visits
+        // every item exactly once.
+        let start = self.slots.iter().position(Option::is_none).unwrap();
+        for offset in 1..=self.slots.len() {
+            let index = (start + offset) & self.mask();
+            while let Some(bucket) = &mut self.slots[index] {
+                if f(&mut bucket.value) {
+                    break;
+                }
+                self.remove(index);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.slots.iter_mut().for_each(|slot| *slot = None);
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slots: self.slots.iter(),
+            len: self.len,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.slots.iter_mut(),
+            len: self.len,
+        }
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    fn ideal_slot(&self, hash: u64) -> usize {
+        hash as usize & self.mask()
+    }
+
+    fn next_slot(&self, index: usize) -> usize {
+        (index + 1) & self.mask()
+    }
+
+    fn distance(&self, hash: u64, index: usize) -> usize {
+        index.wrapping_sub(self.ideal_slot(hash)) & self.mask()
+    }
+
+    fn resize(&mut self, slots: usize) {
+        let old = mem::replace(&mut self.slots, empty_slots(slots));
+        self.len = 0;
+        for bucket in old.into_iter().flatten() {
+            self.insert_no_grow(bucket);
+        }
+    }
+
+    fn insert_no_grow(&mut self, mut bucket: Bucket<T>) -> usize {
+        let mut index = self.ideal_slot(bucket.hash);
+        let mut dist = 0;
+        let mut placed = None;
+        loop {
+            let existing_dist = match &self.slots[index] {
+                Some(existing) => self.distance(existing.hash, index),
+                None => {
+                    self.slots[index] = Some(bucket);
+                    self.len += 1;
+                    return placed.unwrap_or(index);
+                }
+            };
+
+            if existing_dist < dist {
+                // take from the rich: the closer item moves on instead
+                let existing = self.slots[index].as_mut().unwrap();
+                mem::swap(existing, &mut bucket);
+                placed.get_or_insert(index);
+                dist = existing_dist;
+            }
+            index = self.next_slot(index);
+            dist += 1;
+        }
+    }
+}
+
+fn slots_for(len: usize) -> usize {
+    let slots = len
+        .checked_mul(MAX_LOAD_DEN)
+        .expect("capacity overflow")
+        .div_ceil(MAX_LOAD_NUM);
+    slots.next_power_of_two().max(MIN_SLOTS)
+}
+
+fn empty_slots<T>(slots: usize) -> Vec<Option<Bucket<T>>> {
+    iter::repeat_with(|| None).take(slots).collect()
+}
+
+impl<T> Default for RawTable<T> {
+    fn default() -> RawTable<T> {
+        RawTable::new()
+    }
+}
+
+impl<T> IntoIterator for RawTable<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            slots: self.slots.into_iter(),
+            len: self.len,
+        }
+    }
+}
+
+// The iterators walk the slots in table order, which has nothing to do with
+// insertion order and changes whenever the table is resized.
+pub struct Iter<'a, T> {
+    slots: slice::Iter<'a, Option<Bucket<T>>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let bucket = self.slots.by_ref().flatten().next()?;
+        self.len -= 1;
+        Some(&bucket.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter {
+            slots: self.slots.clone(),
+            len: self.len,
+        }
+    }
+}
+
+pub struct IterMut<'a, T> {
+    slots: slice::IterMut<'a, Option<Bucket<T>>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        let bucket = self.slots.by_ref().flatten().next()?;
+        self.len -= 1;
+        Some(&mut bucket.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+pub struct IntoIter<T> {
+    slots: vec::IntoIter<Option<Bucket<T>>>,
+    len: usize,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        let bucket = self.slots.by_ref().flatten().next()?;
+        self.len -= 1;
+        Some(bucket.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
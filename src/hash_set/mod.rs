@@ -0,0 +1,380 @@
+#![allow(dead_code)]
+
+use std::{
+    borrow::Borrow,
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::FromIterator,
+    ops::{BitAnd, BitOr, BitXor, Sub},
+};
+
+use crate::hash_map::{self, HashMap};
+
+pub struct HashSet<T, S = RandomState> {
+    map: HashMap<T, (), S>,
+}
+
+impl<T> HashSet<T> {
+    pub fn new() -> HashSet<T> {
+        HashSet {
+            map: HashMap::new(),
+        }
+    }
+
+    pub fn with_capacity(cap: usize) -> HashSet<T> {
+        HashSet {
+            map: HashMap::with_capacity(cap),
+        }
+    }
+}
+
+impl<T, S> HashSet<T, S> {
+    pub fn with_hasher(hash_builder: S) -> HashSet<T, S> {
+        HashSet {
+            map: HashMap::with_hasher(hash_builder),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    pub fn hasher(&self) -> &S {
+        self.map.hasher()
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.map.reserve(additional);
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.map.retain(|item, _| f(item));
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.map.iter(),
+        }
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> HashSet<T, S> {
+    // returns whether the item was newly added; an equal item already in the
+    // set is kept
+    pub fn insert(&mut self, item: T) -> bool {
+        match self.map.entry(item) {
+            hash_map::Entry::Occupied(_) => false,
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(());
+                true
+            }
+        }
+    }
+
+    // like `insert`, but an equal item already in the set is swapped out
+    pub fn replace(&mut self, item: T) -> Option<T> {
+        let old = self.map.remove_entry(&item).map(|(old, _)| old);
+        self.map.insert(item, ());
+        old
+    }
+
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(item)
+    }
+
+    pub fn get<Q>(&self, item: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get_key_value(item).map(|(item, _)| item)
+    }
+
+    pub fn remove<Q>(&mut self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove(item).is_some()
+    }
+
+    pub fn take<Q>(&mut self, item: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.remove_entry(item).map(|(item, _)| item)
+    }
+
+    // the algebra below yields items lazily, borrowing both sets
+
+    pub fn union<'a>(&'a self, other: &'a HashSet<T, S>) -> impl Iterator<Item = &'a T> {
+        self.iter().chain(other.difference(self))
+    }
+
+    pub fn intersection<'a>(&'a self, other: &'a HashSet<T, S>) -> impl Iterator<Item = &'a T> {
+        // probe the larger set with the items of the smaller one
+        let (small, large) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        small.iter().filter(move |item| large.contains(*item))
+    }
+
+    pub fn difference<'a>(&'a self, other: &'a HashSet<T, S>) -> impl Iterator<Item = &'a T> {
+        self.iter().filter(move |item| !other.contains(*item))
+    }
+
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a HashSet<T, S>,
+    ) -> impl Iterator<Item = &'a T> {
+        self.difference(other).chain(other.difference(self))
+    }
+
+    pub fn is_disjoint(&self, other: &HashSet<T, S>) -> bool {
+        self.intersection(other).next().is_none()
+    }
+
+    pub fn is_subset(&self, other: &HashSet<T, S>) -> bool {
+        self.len() <= other.len() && self.iter().all(|item| other.contains(item))
+    }
+
+    pub fn is_superset(&self, other: &HashSet<T, S>) -> bool {
+        other.is_subset(self)
+    }
+}
+
+impl<T, S: Default> Default for HashSet<T, S> {
+    fn default() -> HashSet<T, S> {
+        HashSet::with_hasher(S::default())
+    }
+}
+
+impl<T: Clone, S: Clone> Clone for HashSet<T, S> {
+    fn clone(&self) -> HashSet<T, S> {
+        HashSet {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug, S> fmt::Debug for HashSet<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> PartialEq for HashSet<T, S> {
+    fn eq(&self, other: &HashSet<T, S>) -> bool {
+        self.len() == other.len() && self.is_subset(other)
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Eq for HashSet<T, S> {}
+
+impl<T: Hash + Eq, S: BuildHasher + Default> FromIterator<T> for HashSet<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> HashSet<T, S> {
+        let mut set = HashSet::default();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Hash + Eq, S: BuildHasher> Extend<T> for HashSet<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.map.extend(iter.into_iter().map(|item| (item, ())));
+    }
+}
+
+impl<'a, T: Hash + Eq + Copy + 'a, S: BuildHasher> Extend<&'a T> for HashSet<T, S> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T: Hash + Eq, const N: usize> From<[T; N]> for HashSet<T> {
+    fn from(items: [T; N]) -> HashSet<T> {
+        items.into_iter().collect()
+    }
+}
+
+// `&a | &b` and friends build a new set, cloning the items
+macro_rules! set_operator {
+    ($trait:ident, $method:ident, $algebra:ident) => {
+        impl<T, S> $trait<&HashSet<T, S>> for &HashSet<T, S>
+        where
+            T: Hash + Eq + Clone,
+            S: BuildHasher + Default,
+        {
+            type Output = HashSet<T, S>;
+
+            fn $method(self, other: &HashSet<T, S>) -> HashSet<T, S> {
+                self.$algebra(other).cloned().collect()
+            }
+        }
+    };
+}
+
+set_operator!(BitOr, bitor, union);
+set_operator!(BitAnd, bitand, intersection);
+set_operator!(Sub, sub, difference);
+set_operator!(BitXor, bitxor, symmetric_difference);
+
+pub struct Iter<'a, T> {
+    inner: hash_map::Iter<'a, T, ()>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next().map(|(item, _)| item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+pub struct IntoIter<T> {
+    inner: hash_map::IntoIter<T, ()>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.inner.next().map(|(item, _)| item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T, S> IntoIterator for HashSet<T, S> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter {
+            inner: self.map.into_iter(),
+        }
+    }
+}
+
+impl<'a, T, S> IntoIterator for &'a HashSet<T, S> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HashSet;
+    use crate::vec::Vec;
+
+    fn sorted<'a>(items: impl Iterator<Item = &'a u32>) -> Vec<u32> {
+        let mut items: Vec<u32> = items.copied().collect();
+        items.sort();
+        items
+    }
+
+    #[test]
+    fn insert_contains_remove() {
+        let mut set = HashSet::new();
+        assert!(set.insert(String::from("a")));
+        assert!(set.insert(String::from("b")));
+        assert!(!set.insert(String::from("a")));
+        assert_eq!(set.len(), 2);
+
+        assert!(set.contains("a"));
+        assert_eq!(set.get("b"), Some(&String::from("b")));
+        assert_eq!(set.replace(String::from("b")), Some(String::from("b")));
+        assert!(set.remove("a"));
+        assert!(!set.remove("a"));
+        assert_eq!(set.take("b"), Some(String::from("b")));
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn algebra() {
+        let a: HashSet<u32> = (0..10).collect();
+        let b: HashSet<u32> = (5..15).collect();
+
+        assert_eq!(sorted(a.union(&b)), (0..15).collect::<Vec<_>>());
+        assert_eq!(sorted(a.intersection(&b)), (5..10).collect::<Vec<_>>());
+        assert_eq!(sorted(b.intersection(&a)), (5..10).collect::<Vec<_>>());
+        assert_eq!(sorted(a.difference(&b)), (0..5).collect::<Vec<_>>());
+        assert_eq!(
+            sorted(a.symmetric_difference(&b)),
+            (0..5).chain(10..15).collect::<Vec<_>>()
+        );
+
+        assert_eq!(&a | &b, (0..15).collect());
+        assert_eq!(&a & &b, (5..10).collect());
+        assert_eq!(&a - &b, (0..5).collect());
+        assert_eq!(&a ^ &b, (0..5).chain(10..15).collect());
+    }
+
+    #[test]
+    fn relations() {
+        let small = HashSet::from([1, 2]);
+        let large = HashSet::from([1, 2, 3]);
+        let other = HashSet::from([7]);
+
+        assert!(small.is_subset(&large));
+        assert!(!large.is_subset(&small));
+        assert!(large.is_superset(&small));
+        assert!(small.is_disjoint(&other));
+        assert!(!small.is_disjoint(&large));
+        assert_eq!(small, HashSet::from([2, 1]));
+        assert_ne!(small, large);
+    }
+
+    #[test]
+    fn iteration() {
+        let mut set: HashSet<u32> = (0..50).collect();
+        set.extend(&[10, 60]);
+        set.retain(|n| n % 2 == 0);
+        assert_eq!(set.iter().len(), 26);
+        assert_eq!(
+            sorted(set.iter()),
+            (0..=60)
+                .step_by(2)
+                .filter(|&n| n < 50 || n == 60)
+                .collect::<Vec<_>>()
+        );
+
+        let mut owned: Vec<u32> = set.clone().into_iter().collect();
+        owned.sort();
+        assert_eq!(owned, sorted(set.iter()));
+        assert_eq!(format!("{:?}", HashSet::from([1])), "{1}");
+    }
+}
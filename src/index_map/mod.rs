@@ -0,0 +1,673 @@
+#![allow(dead_code)]
+
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    collections::hash_map::RandomState,
+    fmt,
+    hash::{BuildHasher, Hash},
+    iter::FromIterator,
+    mem,
+    ops::Index,
+    slice,
+};
+
+use crate::{hash_map::RawTable, sort, vec::Vec};
+
+#[derive(Clone)]
+struct Bucket<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+// Entries sit in a `vec::Vec` in insertion order and the hash table only maps
+// hashes to positions in it, so iteration follows insertion order and every
+// entry also has a stable index until something before it is removed.
+pub struct IndexMap<K, V, S = RandomState> {
+    entries: Vec<Bucket<K, V>>,
+    indices: RawTable<usize>,
+    hash_builder: S,
+}
+
+impl<K, V> IndexMap<K, V> {
+    pub fn new() -> IndexMap<K, V> {
+        IndexMap::with_hasher(RandomState::new())
+    }
+}
+
+impl<K, V, S> IndexMap<K, V, S> {
+    pub fn with_hasher(hash_builder: S) -> IndexMap<K, V, S> {
+        IndexMap {
+            entries: Vec::new(),
+            indices: RawTable::new(),
+            hash_builder,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.len() == 0
+    }
+
+    pub fn reserve(&mut self, additional: usize) {
+        self.entries.reserve_additional(additional);
+        self.indices.reserve(additional);
+    }
+
+    pub fn clear(&mut self) {
+        while self.entries.pop().is_some() {}
+        self.indices.clear();
+    }
+
+    pub fn get_index(&self, index: usize) -> Option<(&K, &V)> {
+        self.entries
+            .get(index)
+            .map(|bucket| (&bucket.key, &bucket.value))
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<(&K, &mut V)> {
+        self.entries
+            .get_mut(index)
+            .map(|bucket| (&bucket.key, &mut bucket.value))
+    }
+
+    pub fn first(&self) -> Option<(&K, &V)> {
+        self.get_index(0)
+    }
+
+    pub fn last(&self) -> Option<(&K, &V)> {
+        self.len()
+            .checked_sub(1)
+            .and_then(|index| self.get_index(index))
+    }
+
+    pub fn pop(&mut self) -> Option<(K, V)> {
+        let bucket = self.entries.pop()?;
+        let slot = self.slot_of(bucket.hash, self.len());
+        self.indices.remove(slot);
+        Some((bucket.key, bucket.value))
+    }
+
+    // removes the entry at `index` by moving the last entry into its place, O(1)
+    pub fn swap_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        let hash = self.entries.get(index)?.hash;
+        let slot = self.slot_of(hash, index);
+        self.indices.remove(slot);
+
+        let last = self.len() - 1;
+        if index != last {
+            let moved = self.slot_of(self.entries[last].hash, last);
+            *self.indices.get_mut(moved) = index;
+            self.entries.swap(index, last);
+        }
+        let bucket = self.entries.pop().unwrap();
+        Some((bucket.key, bucket.value))
+    }
+
+    // removes the entry at `index` and moves all later entries down, O(n)
+    pub fn shift_remove_index(&mut self, index: usize) -> Option<(K, V)> {
+        let hash = self.entries.get(index)?.hash;
+        let slot = self.slot_of(hash, index);
+        self.indices.remove(slot);
+
+        for position in self.indices.iter_mut() {
+            if *position > index {
+                *position -= 1;
+            }
+        }
+        let bucket = self.entries.remove(index);
+        Some((bucket.key, bucket.value))
+    }
+
+    // Compacts in place, keeping the order of what's left. Dropping the
+    // rejected entries and rebuilding the indices is left to a guard, so the
+    // map stays consistent even if `f` panics.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        let mut retain = Retain {
+            map: self,
+            kept: 0,
+            visited: 0,
+        };
+        while retain.visited < retain.map.entries.len() {
+            let bucket = &mut retain.map.entries[retain.visited];
+            let keep = f(&bucket.key, &mut bucket.value);
+            if keep {
+                retain.map.entries.swap(retain.kept, retain.visited);
+                retain.kept += 1;
+            }
+            retain.visited += 1;
+        }
+    }
+
+    // stable, so entries with equal keys keep their relative order
+    pub fn sort_by<F>(&mut self, mut compare: F)
+    where
+        F: FnMut(&K, &V, &K, &V) -> Ordering,
+    {
+        sort::tim_sort_by(&mut self.entries, |a, b| {
+            compare(&a.key, &a.value, &b.key, &b.value)
+        });
+        self.rebuild_indices();
+    }
+
+    pub fn sort_keys(&mut self)
+    where
+        K: Ord,
+    {
+        self.sort_by(|a, _, b, _| a.cmp(b));
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            entries: self.entries.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            entries: self.entries.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator {
+        self.iter_mut().map(|(_, value)| value)
+    }
+
+    // the table slot that points at entry `index`
+    fn slot_of(&self, hash: u64, index: usize) -> usize {
+        self.indices
+            .find(hash, |&position| position == index)
+            .expect("entry missing from the index table")
+    }
+
+    fn rebuild_indices(&mut self) {
+        self.indices.clear();
+        for (index, bucket) in self.entries.iter().enumerate() {
+            self.indices.insert(bucket.hash, index);
+        }
+    }
+}
+
+// Entries before `kept` stay, those up to `visited` were rejected, and the
+// rest weren't looked at (only if the predicate panicked).
+struct Retain<'a, K, V, S> {
+    map: &'a mut IndexMap<K, V, S>,
+    kept: usize,
+    visited: usize,
+}
+
+impl<K, V, S> Drop for Retain<'_, K, V, S> {
+    fn drop(&mut self) {
+        let entries = &mut self.map.entries;
+        for index in self.visited..entries.len() {
+            entries.swap(self.kept + index - self.visited, index);
+        }
+        for _ in self.kept..self.visited {
+            entries.pop();
+        }
+        self.map.rebuild_indices();
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> IndexMap<K, V, S> {
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_full(key, value).1
+    }
+
+    // an existing key keeps its position, a new one is appended;
+    // returns the entry's index along with any value it replaced
+    pub fn insert_full(&mut self, key: K, value: V) -> (usize, Option<V>) {
+        let hash = self.hash_builder.hash_one(&key);
+        let entries = &self.entries;
+        match self.indices.find(hash, |&index| entries[index].key == key) {
+            Some(slot) => {
+                let index = *self.indices.get(slot);
+                let old = mem::replace(&mut self.entries[index].value, value);
+                (index, Some(old))
+            }
+            None => {
+                let index = self.len();
+                self.indices.insert(hash, index);
+                self.entries.push(Bucket { hash, key, value });
+                (index, None)
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        Some(&self.entries[index].value)
+    }
+
+    pub fn get_full<Q>(&self, key: &Q) -> Option<(usize, &K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        let bucket = &self.entries[index];
+        Some((index, &bucket.key, &bucket.value))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        Some(&mut self.entries[index].value)
+    }
+
+    pub fn get_index_of<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let hash = self.hash_builder.hash_one(key);
+        let slot = self
+            .indices
+            .find(hash, |&index| self.entries[index].key.borrow() == key)?;
+        Some(*self.indices.get(slot))
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_index_of(key).is_some()
+    }
+
+    pub fn swap_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        self.swap_remove_index(index).map(|(_, value)| value)
+    }
+
+    pub fn shift_remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let index = self.get_index_of(key)?;
+        self.shift_remove_index(index).map(|(_, value)| value)
+    }
+}
+
+impl<K, V, S: Default> Default for IndexMap<K, V, S> {
+    fn default() -> IndexMap<K, V, S> {
+        IndexMap::with_hasher(S::default())
+    }
+}
+
+impl<K: Clone, V: Clone, S: Clone> Clone for IndexMap<K, V, S> {
+    fn clone(&self) -> IndexMap<K, V, S> {
+        IndexMap {
+            entries: self.entries.clone(),
+            indices: self.indices.clone(),
+            hash_builder: self.hash_builder.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for IndexMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// compares as maps, the order of the entries doesn't matter
+impl<K: Hash + Eq, V: PartialEq, S: BuildHasher> PartialEq for IndexMap<K, V, S> {
+    fn eq(&self, other: &IndexMap<K, V, S>) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K: Hash + Eq, V: Eq, S: BuildHasher> Eq for IndexMap<K, V, S> {}
+
+impl<K, Q, V, S> Index<&Q> for IndexMap<K, V, S>
+where
+    K: Hash + Eq + Borrow<Q>,
+    Q: Hash + Eq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher + Default> FromIterator<(K, V)> for IndexMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> IndexMap<K, V, S> {
+        let mut map = IndexMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Extend<(K, V)> for IndexMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        self.reserve(lower);
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, const N: usize> From<[(K, V); N]> for IndexMap<K, V> {
+    fn from(entries: [(K, V); N]) -> IndexMap<K, V> {
+        entries.into_iter().collect()
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    entries: slice::Iter<'a, Bucket<K, V>>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.entries
+            .next()
+            .map(|bucket| (&bucket.key, &bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries
+            .next_back()
+            .map(|bucket| (&bucket.key, &bucket.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+    entries: slice::IterMut<'a, Bucket<K, V>>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        self.entries
+            .next()
+            .map(|bucket| (&bucket.key, &mut bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries
+            .next_back()
+            .map(|bucket| (&bucket.key, &mut bucket.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+pub struct IntoIter<K, V> {
+    entries: crate::vec::IntoIter<Bucket<K, V>>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.entries.next().map(|bucket| (bucket.key, bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IntoIter<K, V> {
+    fn next_back(&mut self) -> Option<(K, V)> {
+        self.entries
+            .next_back()
+            .map(|bucket| (bucket.key, bucket.value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+
+impl<K, V, S> IntoIterator for IndexMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            entries: self.entries.into_iter(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a IndexMap<K, V, S> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut IndexMap<K, V, S> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::IndexMap;
+    use crate::vec::Vec;
+
+    fn keys<V>(map: &IndexMap<&'static str, V>) -> Vec<&'static str> {
+        map.keys().copied().collect()
+    }
+
+    #[test]
+    fn insertion_order() {
+        let mut map = IndexMap::new();
+        assert_eq!(map.insert_full("c", 1), (0, None));
+        assert_eq!(map.insert_full("a", 2), (1, None));
+        assert_eq!(map.insert_full("b", 3), (2, None));
+        assert_eq!(map.insert_full("a", 20), (1, Some(2)));
+
+        assert_eq!(keys(&map), ["c", "a", "b"]);
+        assert_eq!(map.get_index(1), Some((&"a", &20)));
+        assert_eq!(map.get_full("b"), Some((2, &"b", &3)));
+        assert_eq!(map.get_index_of("c"), Some(0));
+        assert_eq!(map.first(), Some((&"c", &1)));
+        assert_eq!(map.last(), Some((&"b", &3)));
+        assert_eq!(map["a"], 20);
+        assert!(map.values().rev().copied().eq([3, 20, 1]));
+    }
+
+    #[test]
+    fn extend_grows_by_doubling() {
+        let mut map = IndexMap::new();
+        for n in 0..100u32 {
+            map.extend([(n, n)]);
+            assert!(map.entries.capacity().is_power_of_two());
+        }
+        map.reserve(1);
+        assert_eq!(map.entries.capacity(), 128);
+        assert!(map.values().copied().eq(0..100));
+    }
+
+    #[test]
+    fn swap_remove() {
+        let mut map: IndexMap<&str, u32> = IndexMap::from([("a", 1), ("b", 2), ("c", 3), ("d", 4)]);
+        assert_eq!(map.swap_remove("b"), Some(2));
+        assert_eq!(keys(&map), ["a", "d", "c"]);
+        assert_eq!(map.get_index_of("d"), Some(1));
+        assert_eq!(map.swap_remove("b"), None);
+
+        assert_eq!(map.swap_remove_index(2), Some(("c", 3)));
+        assert_eq!(map.swap_remove_index(2), None);
+        assert_eq!(map.pop(), Some(("d", 4)));
+        assert_eq!(keys(&map), ["a"]);
+        assert_eq!(map["a"], 1);
+    }
+
+    #[test]
+    fn shift_remove() {
+        let mut map: IndexMap<&str, u32> = IndexMap::from([("a", 1), ("b", 2), ("c", 3), ("d", 4)]);
+        assert_eq!(map.shift_remove("b"), Some(2));
+        assert_eq!(keys(&map), ["a", "c", "d"]);
+        assert_eq!(map.get_index_of("c"), Some(1));
+        assert_eq!(map.get_index_of("d"), Some(2));
+
+        assert_eq!(map.shift_remove_index(0), Some(("a", 1)));
+        assert_eq!(keys(&map), ["c", "d"]);
+        assert_eq!(map["d"], 4);
+        assert_eq!(map.shift_remove("a"), None);
+    }
+
+    #[test]
+    fn sorting() {
+        let mut map: IndexMap<&str, u32> = IndexMap::from([("d", 1), ("b", 2), ("a", 3), ("c", 2)]);
+        map.sort_keys();
+        assert_eq!(keys(&map), ["a", "b", "c", "d"]);
+        assert_eq!(map.get_index_of("d"), Some(3));
+
+        map.sort_by(|_, a, _, b| a.cmp(b));
+        assert_eq!(keys(&map), ["d", "b", "c", "a"]);
+        assert_eq!(map.get_full("a"), Some((3, &"a", &3)));
+    }
+
+    #[test]
+    fn against_std() {
+        let mut map = IndexMap::new();
+        let mut order: Vec<u32> = Vec::new();
+        let mut expected = std::collections::HashMap::new();
+
+        for step in 0..3000u32 {
+            let key = (step * 7919) % 211;
+            match step % 5 {
+                0..=2 => {
+                    if expected.insert(key, step).is_none() {
+                        order.push(key);
+                    }
+                    map.insert(key, step);
+                }
+                3 => {
+                    let removed = map.shift_remove(&key);
+                    assert_eq!(removed, expected.remove(&key));
+                    if removed.is_some() {
+                        let index = order.iter().position(|&k| k == key).unwrap();
+                        order.remove(index);
+                    }
+                }
+                _ => {
+                    let removed = map.swap_remove(&key);
+                    assert_eq!(removed, expected.remove(&key));
+                    if removed.is_some() {
+                        let index = order.iter().position(|&k| k == key).unwrap();
+                        let last = order.len() - 1;
+                        order.swap(index, last);
+                        order.pop();
+                    }
+                }
+            }
+            assert_eq!(map.len(), expected.len());
+        }
+
+        assert!(map.keys().eq(order.iter()));
+        for (index, key) in order.iter().enumerate() {
+            assert_eq!(map.get_full(key), Some((index, key, &expected[key])));
+        }
+    }
+
+    #[test]
+    fn retain_and_iteration() {
+        let mut map: IndexMap<u32, u32> = (0..20).map(|n| (n, n)).collect();
+        map.retain(|&k, v| {
+            *v *= 10;
+            k % 3 == 0
+        });
+        assert!(map.keys().copied().eq([0, 3, 6, 9, 12, 15, 18]));
+        assert_eq!(map.get_index_of(&9), Some(3));
+
+        for (_, value) in &mut map {
+            *value += 1;
+        }
+        assert_eq!(map[&18], 181);
+        let owned: Vec<(u32, u32)> = map.clone().into_iter().rev().collect();
+        assert_eq!(owned[0], (18, 181));
+
+        let mut reordered = map.clone();
+        reordered.sort_by(|a, _, b, _| b.cmp(a));
+        assert_eq!(map, reordered);
+        assert_eq!(
+            format!("{:?}", IndexMap::from([(2, 'b'), (1, 'a')])),
+            "{2: 'b', 1: 'a'}"
+        );
+
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get(&0), None);
+    }
+
+    #[test]
+    fn retain_survives_a_panicking_predicate() {
+        let mut map: IndexMap<u32, u32> = (0..10).map(|n| (n, n)).collect();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            map.retain(|&k, _| {
+                assert!(k < 6, "predicate failed");
+                k % 2 == 0
+            })
+        }));
+        assert!(result.is_err());
+
+        // whatever was visited is filtered, the rest is still there
+        assert_eq!(map.len(), 7);
+        assert!(map.keys().copied().eq([0, 2, 4, 6, 7, 8, 9]));
+        for (index, key) in [0, 2, 4, 6, 7, 8, 9].iter().enumerate() {
+            assert_eq!(map.get_index_of(key), Some(index));
+        }
+        assert_eq!(map.get(&1), None);
+        map.insert(1, 1);
+        assert_eq!(map.get_index_of(&1), Some(7));
+    }
+}
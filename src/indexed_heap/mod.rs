@@ -0,0 +1,339 @@
+#![allow(dead_code)]
+
+use crate::{
+    slot_map::{Key, SlotMap},
+    vec::Vec,
+};
+
+pub mod pairing;
+
+// Handles stay valid until their item is popped or removed; stale handles
+// are rejected rather than reaching whichever item reuses the slot.
+pub type Handle = Key;
+
+// Shared interface of the heaps in this module. Items come out lowest
+// priority first, as Dijkstra and event simulations want them.
+pub trait IndexedPriorityQueue<T, P: Ord> {
+    fn len(&self) -> usize;
+    fn push(&mut self, item: T, priority: P) -> Handle;
+    fn peek(&self) -> Option<(&T, &P)>;
+    fn pop(&mut self) -> Option<(T, P)>;
+    fn get(&self, handle: Handle) -> Option<(&T, &P)>;
+    // returns the old priority, or `None` for a stale handle
+    fn change_priority(&mut self, handle: Handle, priority: P) -> Option<P>;
+    fn remove(&mut self, handle: Handle) -> Option<(T, P)>;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn contains(&self, handle: Handle) -> bool {
+        self.get(handle).is_some()
+    }
+}
+
+struct Entry<T, P> {
+    item: T,
+    priority: P,
+    pos: usize,
+}
+
+// An implicit heap with `D` children per node over handles into a slot map,
+// each entry remembering its position so it can be found and re-sifted in
+// O(log n). Wider nodes make pushes and decrease-key cheaper (shallower tree)
+// at the cost of more comparisons per level when popping.
+pub struct IndexedHeap<T, P, const D: usize = 4> {
+    heap: Vec<Handle>,
+    entries: SlotMap<Entry<T, P>>,
+}
+
+impl<T, P: Ord> IndexedHeap<T, P> {
+    pub fn new() -> IndexedHeap<T, P> {
+        IndexedHeap::with_arity()
+    }
+}
+
+impl<T, P: Ord, const D: usize> IndexedHeap<T, P, D> {
+    pub fn with_arity() -> IndexedHeap<T, P, D> {
+        assert!(D >= 2, "a heap needs at least two children per node");
+        IndexedHeap {
+            heap: Vec::new(),
+            entries: SlotMap::new(),
+        }
+    }
+
+    pub fn priority(&self, handle: Handle) -> Option<&P> {
+        self.entries.get(handle).map(|entry| &entry.priority)
+    }
+
+    fn priority_at(&self, pos: usize) -> &P {
+        &self.entries[self.heap[pos]].priority
+    }
+
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        self.entries[self.heap[a]].pos = a;
+        self.entries[self.heap[b]].pos = b;
+    }
+
+    fn sift_up(&mut self, mut pos: usize) -> usize {
+        while pos > 0 {
+            let parent = (pos - 1) / D;
+            if self.priority_at(pos) >= self.priority_at(parent) {
+                break;
+            }
+            self.swap(pos, parent);
+            pos = parent;
+        }
+        pos
+    }
+
+    fn sift_down(&mut self, mut pos: usize) {
+        loop {
+            let first = pos * D + 1;
+            if first >= self.heap.len() {
+                break;
+            }
+
+            let last = (first + D).min(self.heap.len());
+            let child = (first + 1..last).fold(first, |best, child| {
+                if self.priority_at(child) < self.priority_at(best) {
+                    child
+                } else {
+                    best
+                }
+            });
+            if self.priority_at(child) >= self.priority_at(pos) {
+                break;
+            }
+            self.swap(pos, child);
+            pos = child;
+        }
+    }
+
+    fn resift(&mut self, pos: usize) {
+        if self.sift_up(pos) == pos {
+            self.sift_down(pos);
+        }
+    }
+}
+
+impl<T, P: Ord, const D: usize> IndexedPriorityQueue<T, P> for IndexedHeap<T, P, D> {
+    fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    fn push(&mut self, item: T, priority: P) -> Handle {
+        let pos = self.heap.len();
+        let handle = self.entries.insert(Entry {
+            item,
+            priority,
+            pos,
+        });
+        self.heap.push(handle);
+        self.sift_up(pos);
+        handle
+    }
+
+    fn peek(&self) -> Option<(&T, &P)> {
+        self.heap.first().and_then(|&handle| self.get(handle))
+    }
+
+    fn pop(&mut self) -> Option<(T, P)> {
+        let top = *self.heap.first()?;
+        self.remove(top)
+    }
+
+    fn get(&self, handle: Handle) -> Option<(&T, &P)> {
+        self.entries
+            .get(handle)
+            .map(|entry| (&entry.item, &entry.priority))
+    }
+
+    fn change_priority(&mut self, handle: Handle, priority: P) -> Option<P> {
+        let entry = self.entries.get_mut(handle)?;
+        let old = std::mem::replace(&mut entry.priority, priority);
+        let pos = entry.pos;
+        self.resift(pos);
+        Some(old)
+    }
+
+    fn remove(&mut self, handle: Handle) -> Option<(T, P)> {
+        let pos = self.entries.get(handle)?.pos;
+        let last = self.heap.len() - 1;
+        self.swap(pos, last);
+        self.heap.pop();
+        if pos < last {
+            self.resift(pos);
+        }
+
+        let entry = self.entries.remove(handle).unwrap();
+        Some((entry.item, entry.priority))
+    }
+}
+
+impl<T, P: Ord> Default for IndexedHeap<T, P> {
+    fn default() -> IndexedHeap<T, P> {
+        IndexedHeap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{pairing::PairingHeap, Handle, IndexedHeap, IndexedPriorityQueue};
+    use crate::vec::Vec;
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    fn basic<Q: IndexedPriorityQueue<&'static str, u32>>(mut queue: Q) {
+        let a = queue.push("a", 5);
+        let b = queue.push("b", 3);
+        let c = queue.push("c", 8);
+        assert_eq!(queue.len(), 3);
+        assert_eq!(queue.peek(), Some((&"b", &3)));
+
+        assert_eq!(queue.change_priority(c, 1), Some(8));
+        assert_eq!(queue.peek(), Some((&"c", &1)));
+        assert_eq!(queue.change_priority(c, 9), Some(1));
+        assert_eq!(queue.peek(), Some((&"b", &3)));
+
+        assert_eq!(queue.remove(b), Some(("b", 3)));
+        assert_eq!(queue.remove(b), None);
+        assert_eq!(queue.change_priority(b, 0), None);
+        assert!(!queue.contains(b));
+
+        assert_eq!(queue.pop(), Some(("a", 5)));
+        assert!(!queue.contains(a));
+        assert_eq!(queue.get(c), Some((&"c", &9)));
+        assert_eq!(queue.pop(), Some(("c", 9)));
+        assert_eq!(queue.pop(), None);
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn dary_basic() {
+        basic(IndexedHeap::new());
+        basic(IndexedHeap::<_, _, 2>::with_arity());
+        basic(IndexedHeap::<_, _, 8>::with_arity());
+    }
+
+    #[test]
+    fn pairing_basic() {
+        basic(PairingHeap::new());
+    }
+
+    // random pushes, pops, priority changes and removals checked against a plain list
+    fn differential<Q: IndexedPriorityQueue<u64, u64>>(mut queue: Q, seed: u64) {
+        let mut rng = Rng(seed);
+        let mut model: Vec<(Handle, u64, u64)> = Vec::new();
+
+        for step in 0..5000 {
+            match rng.below(10) {
+                0..=3 => {
+                    let priority = rng.below(1000);
+                    let handle = queue.push(step, priority);
+                    model.push((handle, step, priority));
+                }
+                4 | 5 => {
+                    let expected = model.iter().map(|&(_, _, priority)| priority).min();
+                    let popped = queue.pop();
+                    assert_eq!(popped.map(|(_, priority)| priority), expected);
+                    if let Some((item, _)) = popped {
+                        let index = model.iter().position(|&(_, i, _)| i == item).unwrap();
+                        model.remove(index);
+                    }
+                }
+                6..=8 if !model.is_empty() => {
+                    let index = rng.below(model.len() as u64) as usize;
+                    let priority = rng.below(1000);
+                    let (handle, _, old) = model[index];
+                    assert_eq!(queue.change_priority(handle, priority), Some(old));
+                    model[index].2 = priority;
+                }
+                9 if !model.is_empty() => {
+                    let index = rng.below(model.len() as u64) as usize;
+                    let (handle, item, priority) = model.remove(index);
+                    assert_eq!(queue.remove(handle), Some((item, priority)));
+                }
+                _ => {}
+            }
+            assert_eq!(queue.len(), model.len());
+        }
+    }
+
+    #[test]
+    fn dary_differential() {
+        differential(IndexedHeap::new(), 1);
+        differential(IndexedHeap::<_, _, 2>::with_arity(), 2);
+        differential(IndexedHeap::<_, _, 16>::with_arity(), 3);
+    }
+
+    #[test]
+    fn pairing_differential() {
+        differential(PairingHeap::new(), 4);
+    }
+
+    fn dijkstra<Q: IndexedPriorityQueue<usize, u64>>(
+        mut queue: Q,
+        edges: &[(usize, usize, u64)],
+        nodes: usize,
+    ) -> Vec<Option<u64>> {
+        let mut dist: Vec<Option<u64>> = crate::vector![None; nodes];
+        let mut handles: Vec<Option<Handle>> = crate::vector![None; nodes];
+        handles[0] = Some(queue.push(0, 0));
+        dist[0] = Some(0);
+
+        while let Some((node, d)) = queue.pop() {
+            for &(_, to, weight) in edges.iter().filter(|edge| edge.0 == node) {
+                let candidate = d + weight;
+                if dist[to].is_some_and(|best| best <= candidate) {
+                    continue;
+                }
+                dist[to] = Some(candidate);
+                match handles[to] {
+                    Some(handle) if queue.contains(handle) => {
+                        queue.change_priority(handle, candidate);
+                    }
+                    _ => handles[to] = Some(queue.push(to, candidate)),
+                }
+            }
+        }
+        dist
+    }
+
+    #[test]
+    fn shortest_paths() {
+        let edges = [
+            (0, 1, 7),
+            (0, 2, 9),
+            (0, 5, 14),
+            (1, 2, 10),
+            (1, 3, 15),
+            (2, 3, 11),
+            (2, 5, 2),
+            (3, 4, 6),
+            (5, 4, 9),
+        ];
+        let expected = [
+            Some(0),
+            Some(7),
+            Some(9),
+            Some(20),
+            Some(20),
+            Some(11),
+            None,
+        ];
+
+        assert_eq!(dijkstra(IndexedHeap::new(), &edges, 7), expected);
+        assert_eq!(dijkstra(PairingHeap::new(), &edges, 7), expected);
+    }
+}
@@ -0,0 +1,179 @@
+use std::mem;
+
+use super::{Handle, IndexedPriorityQueue};
+use crate::{slot_map::SlotMap, vec::Vec};
+
+struct Node<T, P> {
+    item: T,
+    priority: P,
+    child: Option<Handle>,
+    sibling: Option<Handle>,
+    // parent for the leftmost child, left sibling for the others
+    prev: Option<Handle>,
+}
+
+// A heap-ordered multiway tree whose nodes live in a slot map, so handles are
+// just node keys. Pushes and decrease-key are O(1) melds with the root, pops
+// re-pair the root's children in two passes for amortized O(log n).
+pub struct PairingHeap<T, P> {
+    nodes: SlotMap<Node<T, P>>,
+    root: Option<Handle>,
+}
+
+impl<T, P: Ord> PairingHeap<T, P> {
+    pub fn new() -> PairingHeap<T, P> {
+        PairingHeap {
+            nodes: SlotMap::new(),
+            root: None,
+        }
+    }
+
+    pub fn priority(&self, handle: Handle) -> Option<&P> {
+        self.nodes.get(handle).map(|node| &node.priority)
+    }
+
+    // links two roots, the one with the higher priority becoming the first child
+    fn meld(&mut self, a: Handle, b: Handle) -> Handle {
+        let (parent, child) = if self.nodes[b].priority < self.nodes[a].priority {
+            (b, a)
+        } else {
+            (a, b)
+        };
+
+        let first = self.nodes[parent].child.replace(child);
+        if let Some(first) = first {
+            self.nodes[first].prev = Some(child);
+        }
+        self.nodes[child].sibling = first;
+        self.nodes[child].prev = Some(parent);
+        parent
+    }
+
+    fn meld_root(&mut self, node: Handle) {
+        self.root = Some(match self.root {
+            Some(root) => self.meld(root, node),
+            None => node,
+        });
+    }
+
+    // detaches the subtree under `node` from its parent and siblings
+    fn cut(&mut self, node: Handle) {
+        let Some(prev) = self.nodes[node].prev.take() else {
+            return;
+        };
+        let sibling = self.nodes[node].sibling.take();
+        if self.nodes[prev].child == Some(node) {
+            self.nodes[prev].child = sibling;
+        } else {
+            self.nodes[prev].sibling = sibling;
+        }
+        if let Some(sibling) = sibling {
+            self.nodes[sibling].prev = Some(prev);
+        }
+    }
+
+    // melds the children of `node` pairwise left to right, then folds the
+    // pairs together right to left
+    fn merge_children(&mut self, node: Handle) -> Option<Handle> {
+        let mut pairs = Vec::new();
+        let mut next = self.nodes[node].child.take();
+        while let Some(first) = next {
+            let second = self.nodes[first].sibling;
+            next = second.and_then(|second| self.nodes[second].sibling);
+            for child in [Some(first), second].into_iter().flatten() {
+                self.nodes[child].prev = None;
+                self.nodes[child].sibling = None;
+            }
+            pairs.push(match second {
+                Some(second) => self.meld(first, second),
+                None => first,
+            });
+        }
+
+        let mut merged = pairs.pop()?;
+        while let Some(pair) = pairs.pop() {
+            merged = self.meld(pair, merged);
+        }
+        Some(merged)
+    }
+
+    // takes `node` out of the tree, its children rejoining under the root
+    fn unlink(&mut self, node: Handle) {
+        if self.root == Some(node) {
+            self.root = self.merge_children(node);
+            return;
+        }
+
+        self.cut(node);
+        if let Some(children) = self.merge_children(node) {
+            self.meld_root(children);
+        }
+    }
+}
+
+impl<T, P: Ord> IndexedPriorityQueue<T, P> for PairingHeap<T, P> {
+    fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    fn push(&mut self, item: T, priority: P) -> Handle {
+        let node = self.nodes.insert(Node {
+            item,
+            priority,
+            child: None,
+            sibling: None,
+            prev: None,
+        });
+        self.meld_root(node);
+        node
+    }
+
+    fn peek(&self) -> Option<(&T, &P)> {
+        self.root.and_then(|root| self.get(root))
+    }
+
+    fn pop(&mut self) -> Option<(T, P)> {
+        let root = self.root?;
+        self.remove(root)
+    }
+
+    fn get(&self, handle: Handle) -> Option<(&T, &P)> {
+        self.nodes
+            .get(handle)
+            .map(|node| (&node.item, &node.priority))
+    }
+
+    fn change_priority(&mut self, handle: Handle, priority: P) -> Option<P> {
+        let node = self.nodes.get_mut(handle)?;
+        let decreased = priority < node.priority;
+        let old = mem::replace(&mut node.priority, priority);
+
+        if decreased {
+            // the subtree below stays heap ordered, only the link above may break
+            if self.root != Some(handle) {
+                self.cut(handle);
+                self.meld_root(handle);
+            }
+        } else {
+            self.unlink(handle);
+            self.meld_root(handle);
+        }
+        Some(old)
+    }
+
+    fn remove(&mut self, handle: Handle) -> Option<(T, P)> {
+        if !self.nodes.contains_key(handle) {
+            return None;
+        }
+
+        self.unlink(handle);
+        let node = self.nodes.remove(handle).unwrap();
+        Some((node.item, node.priority))
+    }
+}
+
+impl<T, P: Ord> Default for PairingHeap<T, P> {
+    fn default() -> PairingHeap<T, P> {
+        PairingHeap::new()
+    }
+}
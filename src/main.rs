@@ -2,5 +2,24 @@ mod singly_linked_list;
 mod vec;
 mod bstree;
 mod queue;
+mod vec_deque;
+mod sort;
+mod bit_vec;
+mod slot_map;
+mod persistent_vec;
+mod segmented_vec;
+mod binary_heap;
+mod indexed_heap;
+mod hash_map;
+mod hash_set;
+mod index_map;
+mod sorted_vec_map;
+mod sorted_vec_set;
+mod executor;
+#[cfg(all(
+    any(target_os = "linux", target_os = "macos"),
+    target_pointer_width = "64"
+))]
+mod mmap_vec;
 
 fn main() {}
@@ -0,0 +1,361 @@
+#![allow(dead_code)]
+
+use std::{
+    fmt,
+    fs::{File, OpenOptions},
+    io,
+    marker::PhantomData,
+    mem,
+    ops::{Deref, DerefMut},
+    path::Path,
+    ptr::{self, NonNull},
+    slice,
+};
+
+use crate::vec::growth::{Doubling, GrowthPolicy};
+
+mod sys;
+
+/// Types that are stored as raw bytes and read back from a file.
+///
+/// # Safety
+///
+/// Any bytes of the right size must be a valid value: no pointers or
+/// references, no padding, no invalid bit patterns (so no `bool`, `char` or
+/// enums). `#[repr(C)]` structs made only of such fields qualify.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($t:ty),*) => {
+        $(unsafe impl Pod for $t {})*
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+const MAGIC: [u8; 8] = *b"MMAPVEC1";
+
+// the items start here, which keeps them aligned for any `T` we accept
+const DATA_OFFSET: usize = 64;
+
+// the first growth fills at least a page
+const MIN_FILE_LEN: usize = 4096;
+
+#[repr(C)]
+struct Header {
+    magic: [u8; 8],
+    item_size: u64,
+    len: u64,
+}
+
+// A vector whose items live in a file mapped into memory. The file starts
+// with a header recording the item size and length, the length being kept up
+// to date on every push and pop, so the file can be reopened by a later run.
+// Growing extends the file and maps it again, which invalidates the slices
+// handed out before, like reallocating a `vec::Vec` does.
+//
+// The mapping is shared, so the OS writes changes back on its own schedule;
+// `flush` forces them out to disk.
+pub struct MmapVec<T: Pod> {
+    file: File,
+    map: NonNull<u8>,
+    map_len: usize,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T: Pod> MmapVec<T> {
+    // creates the file, or truncates it if it already exists
+    pub fn create<P: AsRef<Path>>(path: P) -> io::Result<MmapVec<T>> {
+        assert!(mem::size_of::<T>() > 0, "zero-sized items can't be mapped");
+        assert!(
+            mem::align_of::<T>() <= DATA_OFFSET,
+            "item alignment too large"
+        );
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(path)?;
+        file.set_len(DATA_OFFSET as u64)?;
+
+        let vec = MmapVec::map_file(file, DATA_OFFSET, 0)?;
+        unsafe {
+            ptr::write(
+                vec.header(),
+                Header {
+                    magic: MAGIC,
+                    item_size: mem::size_of::<T>() as u64,
+                    len: 0,
+                },
+            );
+        }
+        Ok(vec)
+    }
+
+    // fails with `InvalidData` if the file wasn't written by an `MmapVec` of
+    // items the same size as `T`, or is shorter than its header claims
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<MmapVec<T>> {
+        assert!(mem::size_of::<T>() > 0, "zero-sized items can't be mapped");
+        assert!(
+            mem::align_of::<T>() <= DATA_OFFSET,
+            "item alignment too large"
+        );
+
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        let file_len = usize::try_from(file.metadata()?.len()).map_err(invalid)?;
+        if file_len < DATA_OFFSET {
+            return Err(invalid("file too short for a header"));
+        }
+
+        let mut vec = MmapVec::map_file(file, file_len, 0)?;
+        let header = unsafe { ptr::read(vec.header()) };
+        if header.magic != MAGIC {
+            return Err(invalid("not an MmapVec file"));
+        }
+        if header.item_size != mem::size_of::<T>() as u64 {
+            return Err(invalid("item size doesn't match"));
+        }
+        let len = usize::try_from(header.len).map_err(invalid)?;
+        if len > vec.capacity() {
+            return Err(invalid("file too short for its length"));
+        }
+
+        vec.len = len;
+        Ok(vec)
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn capacity(&self) -> usize {
+        (self.map_len - DATA_OFFSET) / mem::size_of::<T>()
+    }
+
+    pub fn push(&mut self, item: T) -> io::Result<()> {
+        if self.len == self.capacity() {
+            let cap = self.capacity();
+            self.grow(Doubling.next_capacity(cap).max(cap + 1))?;
+        }
+
+        unsafe { ptr::write(self.data().add(self.len), item) };
+        self.set_len(self.len + 1);
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        self.set_len(self.len - 1);
+        unsafe { Some(ptr::read(self.data().add(self.len))) }
+    }
+
+    pub fn clear(&mut self) {
+        self.set_len(0);
+    }
+
+    pub fn extend_from_slice(&mut self, items: &[T]) -> io::Result<()> {
+        self.reserve(self.len + items.len())?;
+        unsafe { ptr::copy_nonoverlapping(items.as_ptr(), self.data().add(self.len), items.len()) };
+        self.set_len(self.len + items.len());
+        Ok(())
+    }
+
+    // makes room for `len` items in total, like `vec::Vec::reserve`
+    pub fn reserve(&mut self, len: usize) -> io::Result<()> {
+        if len > self.capacity() {
+            self.grow(len)?;
+        }
+        Ok(())
+    }
+
+    // writes the mapped pages, header included, back to the file and waits
+    // for it to finish
+    pub fn flush(&self) -> io::Result<()> {
+        unsafe { sys::sync(self.map, self.map_len) }
+    }
+
+    fn map_file(file: File, map_len: usize, len: usize) -> io::Result<MmapVec<T>> {
+        let map = sys::map(&file, map_len)?;
+        Ok(MmapVec {
+            file,
+            map,
+            map_len,
+            len,
+            _marker: PhantomData,
+        })
+    }
+
+    // extends the file to hold `cap` items and maps it again
+    fn grow(&mut self, cap: usize) -> io::Result<()> {
+        let file_len = cap
+            .checked_mul(mem::size_of::<T>())
+            .and_then(|bytes| bytes.checked_add(DATA_OFFSET))
+            .expect("capacity overflow")
+            .max(MIN_FILE_LEN);
+
+        self.file.set_len(file_len as u64)?;
+        let map = sys::map(&self.file, file_len)?;
+        unsafe { sys::unmap(self.map, self.map_len)? };
+        self.map = map;
+        self.map_len = file_len;
+        Ok(())
+    }
+
+    fn set_len(&mut self, len: usize) {
+        self.len = len;
+        unsafe { (*self.header()).len = len as u64 };
+    }
+
+    fn header(&self) -> *mut Header {
+        self.map.as_ptr() as *mut Header
+    }
+
+    fn data(&self) -> *mut T {
+        unsafe { self.map.as_ptr().add(DATA_OFFSET) as *mut T }
+    }
+}
+
+fn invalid<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, error)
+}
+
+impl<T: Pod> Drop for MmapVec<T> {
+    fn drop(&mut self) {
+        // nothing sensible to do about a failure here
+        let _ = unsafe { sys::unmap(self.map, self.map_len) };
+    }
+}
+
+impl<T: Pod> Deref for MmapVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.data(), self.len) }
+    }
+}
+
+impl<T: Pod> DerefMut for MmapVec<T> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        unsafe { slice::from_raw_parts_mut(self.data(), self.len) }
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for MmapVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, io, path::PathBuf};
+
+    use super::{MmapVec, Pod};
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    #[repr(C)]
+    struct Record {
+        id: u32,
+        score: f32,
+        counts: [u16; 4],
+    }
+
+    unsafe impl Pod for Record {}
+
+    // removes the file once the test is done with it, pass or fail
+    struct TempFile(PathBuf);
+
+    impl TempFile {
+        fn new(name: &str) -> TempFile {
+            let file = format!("mmap_vec-{}-{}", std::process::id(), name);
+            TempFile(std::env::temp_dir().join(file))
+        }
+    }
+
+    impl Drop for TempFile {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn push_pop_deref() {
+        let path = TempFile::new("push_pop");
+        let mut vec = MmapVec::create(&path.0).unwrap();
+        assert!(vec.is_empty());
+        for n in 0..10_000u64 {
+            vec.push(n * n).unwrap();
+        }
+        assert_eq!(vec.len(), 10_000);
+        assert!(vec.capacity() >= 10_000);
+        assert_eq!(vec[100], 10_000);
+
+        vec[0] = 7;
+        vec.sort_unstable_by(|a, b| b.cmp(a));
+        assert_eq!(vec.first(), Some(&(9_999 * 9_999)));
+        assert_eq!(vec.pop(), Some(1));
+        assert_eq!(vec.len(), 9_999);
+
+        vec.clear();
+        assert_eq!(vec.pop(), None);
+    }
+
+    #[test]
+    fn reopen() {
+        let path = TempFile::new("reopen");
+        let records: Vec<Record> = (0..1000)
+            .map(|id| Record {
+                id,
+                score: id as f32 / 2.0,
+                counts: [id as u16; 4],
+            })
+            .collect();
+
+        {
+            let mut vec = MmapVec::create(&path.0).unwrap();
+            vec.extend_from_slice(&records).unwrap();
+            vec.pop();
+            vec.flush().unwrap();
+        }
+
+        let mut vec: MmapVec<Record> = MmapVec::open(&path.0).unwrap();
+        assert_eq!(&vec[..], &records[..999]);
+        vec.push(records[999]).unwrap();
+        drop(vec);
+
+        let vec: MmapVec<Record> = MmapVec::open(&path.0).unwrap();
+        assert_eq!(&vec[..], &records[..]);
+    }
+
+    #[test]
+    fn open_validates_header() {
+        let path = TempFile::new("validate");
+        let mut vec = MmapVec::create(&path.0).unwrap();
+        vec.extend_from_slice(&[1u32, 2, 3]).unwrap();
+        drop(vec);
+
+        let wrong_size = MmapVec::<u64>::open(&path.0).unwrap_err();
+        assert_eq!(wrong_size.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(MmapVec::<f32>::open(&path.0).unwrap().len(), 3);
+
+        let file = fs::OpenOptions::new().write(true).open(&path.0).unwrap();
+        file.set_len(64 + 2 * 4).unwrap();
+        let truncated = MmapVec::<u32>::open(&path.0).unwrap_err();
+        assert_eq!(truncated.kind(), io::ErrorKind::InvalidData);
+
+        fs::write(&path.0, [0u8; 100]).unwrap();
+        let garbage = MmapVec::<u32>::open(&path.0).unwrap_err();
+        assert_eq!(garbage.kind(), io::ErrorKind::InvalidData);
+
+        fs::write(&path.0, b"short").unwrap();
+        assert!(MmapVec::<u32>::open(&path.0).is_err());
+    }
+}
@@ -0,0 +1,70 @@
+// The few libc bindings the mapping needs, declared by hand to stay free of
+// dependencies. `off_t` is 64 bits wide on the 64-bit Linux and macOS targets
+// this module is limited to; 32-bit Linux would need `mmap64` instead.
+
+use std::{
+    ffi::{c_int, c_void},
+    fs::File,
+    io,
+    os::unix::io::AsRawFd,
+    ptr::NonNull,
+};
+
+const PROT_READ: c_int = 1;
+const PROT_WRITE: c_int = 2;
+const MAP_SHARED: c_int = 1;
+
+#[cfg(target_os = "linux")]
+const MS_SYNC: c_int = 4;
+#[cfg(target_os = "macos")]
+const MS_SYNC: c_int = 0x10;
+
+extern "C" {
+    fn mmap(
+        addr: *mut c_void,
+        len: usize,
+        prot: c_int,
+        flags: c_int,
+        fd: c_int,
+        offset: i64,
+    ) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+    fn msync(addr: *mut c_void, len: usize, flags: c_int) -> c_int;
+}
+
+// maps the first `len` bytes of `file` read-write and shared, so stores
+// through the mapping end up in the file
+pub fn map(file: &File, len: usize) -> io::Result<NonNull<u8>> {
+    let ptr = unsafe {
+        mmap(
+            std::ptr::null_mut(),
+            len,
+            PROT_READ | PROT_WRITE,
+            MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if ptr as usize == usize::MAX {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(NonNull::new(ptr as *mut u8).expect("mmap returned null"))
+}
+
+// `ptr` and `len` must describe a live mapping made by `map`
+pub unsafe fn unmap(ptr: NonNull<u8>, len: usize) -> io::Result<()> {
+    if munmap(ptr.as_ptr() as *mut c_void, len) == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+// `ptr` and `len` must describe a live mapping made by `map`
+pub unsafe fn sync(ptr: NonNull<u8>, len: usize) -> io::Result<()> {
+    if msync(ptr.as_ptr() as *mut c_void, len, MS_SYNC) == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
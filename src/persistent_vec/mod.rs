@@ -0,0 +1,410 @@
+#![allow(dead_code)]
+
+use std::{fmt, iter::FromIterator, ops::Index, rc::Rc, slice};
+
+use crate::vec::Vec;
+
+const BITS: usize = 5;
+const WIDTH: usize = 1 << BITS;
+const MASK: usize = WIDTH - 1;
+
+#[derive(Clone)]
+enum Node<T> {
+    Branch(Vec<Rc<Node<T>>>),
+    Leaf(Vec<T>),
+}
+
+// A 32-way trie of leaves holding 32 items each, plus a tail leaf for the
+// last (up to) 32 items so most pushes and pops never touch the trie.
+// Nodes are shared between clones and copied on write, so a clone costs
+// two reference count bumps and a write copies one root-to-leaf path.
+pub struct PersistentVec<T> {
+    len: usize,
+    shift: usize,
+    root: Rc<Node<T>>,
+    tail: Rc<Node<T>>,
+}
+
+impl<T> PersistentVec<T> {
+    pub fn new() -> PersistentVec<T> {
+        PersistentVec {
+            len: 0,
+            shift: BITS,
+            root: Rc::new(Node::Branch(Vec::new())),
+            tail: Rc::new(Node::Leaf(Vec::new())),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            Some(&self.leaf_for(index)[index & MASK])
+        } else {
+            None
+        }
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            vec: self,
+            index: 0,
+            leaf: [].iter(),
+        }
+    }
+
+    // whether both vectors still share their whole structure
+    pub fn ptr_eq(&self, other: &PersistentVec<T>) -> bool {
+        Rc::ptr_eq(&self.root, &other.root) && Rc::ptr_eq(&self.tail, &other.tail)
+    }
+
+    fn tail_offset(&self) -> usize {
+        if self.len < WIDTH {
+            0
+        } else {
+            ((self.len - 1) >> BITS) << BITS
+        }
+    }
+
+    fn leaf_for(&self, index: usize) -> &[T] {
+        if index >= self.tail_offset() {
+            return leaf(&self.tail);
+        }
+
+        let mut node = &*self.root;
+        let mut level = self.shift;
+        loop {
+            match node {
+                Node::Branch(children) => {
+                    node = &children[(index >> level) & MASK];
+                    level -= BITS;
+                }
+                Node::Leaf(items) => return items,
+            }
+        }
+    }
+}
+
+impl<T: Clone> PersistentVec<T> {
+    pub fn push(&mut self, value: T) {
+        if self.len - self.tail_offset() < WIDTH {
+            leaf_mut(&mut self.tail).push(value);
+            self.len += 1;
+            return;
+        }
+
+        let full_tail = std::mem::replace(&mut self.tail, Rc::new(Node::Leaf(Vec::new())));
+        let leaf_start = self.len - WIDTH;
+        if leaf_start >> BITS >= 1 << self.shift {
+            let mut children = Vec::new();
+            children.push(self.root.clone());
+            children.push(new_path(self.shift, full_tail));
+            self.root = Rc::new(Node::Branch(children));
+            self.shift += BITS;
+        } else {
+            push_tail(&mut self.root, self.shift, leaf_start, full_tail);
+        }
+
+        leaf_mut(&mut self.tail).push(value);
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let value = leaf_mut(&mut self.tail).pop();
+        self.len -= 1;
+        if leaf(&self.tail).is_empty() && self.len > 0 {
+            // the last leaf of the trie becomes the new tail
+            self.tail = pop_tail(&mut self.root, self.shift, self.len - 1);
+            if let Node::Branch(children) = &*self.root {
+                if self.shift > BITS && children.len() == 1 {
+                    self.root = children[0].clone();
+                    self.shift -= BITS;
+                }
+            }
+        }
+        value
+    }
+
+    pub fn set(&mut self, index: usize, value: T) {
+        assert!(index < self.len, "index out of bounds");
+
+        if index >= self.tail_offset() {
+            leaf_mut(&mut self.tail)[index & MASK] = value;
+        } else {
+            set_in(&mut self.root, self.shift, index, value);
+        }
+    }
+
+    pub fn to_vec(&self) -> Vec<T> {
+        self.iter().cloned().collect()
+    }
+}
+
+fn leaf<T>(node: &Node<T>) -> &Vec<T> {
+    match node {
+        Node::Leaf(items) => items,
+        Node::Branch(_) => unreachable!(),
+    }
+}
+
+fn leaf_mut<T: Clone>(node: &mut Rc<Node<T>>) -> &mut Vec<T> {
+    match Rc::make_mut(node) {
+        Node::Leaf(items) => items,
+        Node::Branch(_) => unreachable!(),
+    }
+}
+
+fn branch_mut<T: Clone>(node: &mut Rc<Node<T>>) -> &mut Vec<Rc<Node<T>>> {
+    match Rc::make_mut(node) {
+        Node::Branch(children) => children,
+        Node::Leaf(_) => unreachable!(),
+    }
+}
+
+fn new_path<T>(level: usize, leaf: Rc<Node<T>>) -> Rc<Node<T>> {
+    if level == 0 {
+        leaf
+    } else {
+        let mut children = Vec::new();
+        children.push(new_path(level - BITS, leaf));
+        Rc::new(Node::Branch(children))
+    }
+}
+
+// hangs `leaf`, which starts at `index`, under the rightmost path of the trie
+fn push_tail<T: Clone>(node: &mut Rc<Node<T>>, level: usize, index: usize, leaf: Rc<Node<T>>) {
+    let children = branch_mut(node);
+    let child = (index >> level) & MASK;
+    if level == BITS {
+        children.push(leaf);
+    } else if child < children.len() {
+        push_tail(&mut children[child], level - BITS, index, leaf);
+    } else {
+        children.push(new_path(level - BITS, leaf));
+    }
+}
+
+// detaches the rightmost leaf, which holds `index`, pruning branches left empty
+fn pop_tail<T: Clone>(node: &mut Rc<Node<T>>, level: usize, index: usize) -> Rc<Node<T>> {
+    let children = branch_mut(node);
+    if level == BITS {
+        return children.pop().unwrap();
+    }
+
+    let child = (index >> level) & MASK;
+    let leaf = pop_tail(&mut children[child], level - BITS, index);
+    if matches!(&*children[child], Node::Branch(grandchildren) if grandchildren.is_empty()) {
+        children.pop();
+    }
+    leaf
+}
+
+fn set_in<T: Clone>(node: &mut Rc<Node<T>>, level: usize, index: usize, value: T) {
+    match Rc::make_mut(node) {
+        Node::Leaf(items) => items[index & MASK] = value,
+        Node::Branch(children) => set_in(
+            &mut children[(index >> level) & MASK],
+            level - BITS,
+            index,
+            value,
+        ),
+    }
+}
+
+impl<T> Clone for PersistentVec<T> {
+    fn clone(&self) -> PersistentVec<T> {
+        PersistentVec {
+            len: self.len,
+            shift: self.shift,
+            root: self.root.clone(),
+            tail: self.tail.clone(),
+        }
+    }
+}
+
+impl<T> Default for PersistentVec<T> {
+    fn default() -> PersistentVec<T> {
+        PersistentVec::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for PersistentVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for PersistentVec<T> {
+    fn eq(&self, other: &PersistentVec<T>) -> bool {
+        self.len == other.len && (self.ptr_eq(other) || self.iter().eq(other.iter()))
+    }
+}
+
+impl<T: Eq> Eq for PersistentVec<T> {}
+
+impl<T> Index<usize> for PersistentVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T: Clone> FromIterator<T> for PersistentVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> PersistentVec<T> {
+        let mut vec = PersistentVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T: Clone> Extend<T> for PersistentVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<T: Clone> From<Vec<T>> for PersistentVec<T> {
+    fn from(vec: Vec<T>) -> PersistentVec<T> {
+        vec.into_iter().collect()
+    }
+}
+
+impl<T: Clone> From<&PersistentVec<T>> for Vec<T> {
+    fn from(vec: &PersistentVec<T>) -> Vec<T> {
+        vec.to_vec()
+    }
+}
+
+pub struct Iter<'a, T> {
+    vec: &'a PersistentVec<T>,
+    index: usize,
+    leaf: slice::Iter<'a, T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.index == self.vec.len {
+            return None;
+        }
+        if self.leaf.len() == 0 {
+            self.leaf = self.vec.leaf_for(self.index).iter();
+        }
+        self.index += 1;
+        self.leaf.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.vec.len - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a PersistentVec<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::PersistentVec;
+    use crate::vec::Vec;
+
+    #[test]
+    fn push_get() {
+        let mut vec = PersistentVec::new();
+        for n in 0..40_000 {
+            vec.push(n);
+        }
+        assert_eq!(vec.len(), 40_000);
+        assert!((0..40_000).all(|n| vec[n] == n));
+        assert_eq!(vec.get(40_000), None);
+        assert_eq!(vec.last(), Some(&39_999));
+        assert!(vec.iter().copied().eq(0..40_000));
+    }
+
+    #[test]
+    fn pop() {
+        let mut vec: PersistentVec<usize> = (0..2000).collect();
+        for n in (0..2000).rev() {
+            assert_eq!(vec.pop(), Some(n));
+            assert_eq!(vec.len(), n);
+            if n % 97 == 0 {
+                assert!(vec.iter().copied().eq(0..n));
+            }
+        }
+        assert_eq!(vec.pop(), None);
+        vec.push(7);
+        assert_eq!(vec[0], 7);
+    }
+
+    #[test]
+    fn snapshots_are_unaffected() {
+        let mut vec: PersistentVec<String> = (0..1100).map(|n| n.to_string()).collect();
+        let snapshot = vec.clone();
+        assert!(vec.ptr_eq(&snapshot));
+
+        vec.set(5, String::from("five"));
+        vec.set(1099, String::from("last"));
+        vec.push(String::from("pushed"));
+        assert!(!vec.ptr_eq(&snapshot));
+
+        assert_eq!(snapshot.len(), 1100);
+        assert_eq!(snapshot[5], "5");
+        assert_eq!(snapshot[1099], "1099");
+        assert_eq!(vec[5], "five");
+        assert_eq!(vec[1099], "last");
+        assert_eq!(vec[1100], "pushed");
+
+        let mut popped = snapshot.clone();
+        (0..200).for_each(|_| {
+            popped.pop();
+        });
+        assert_eq!(snapshot.len(), 1100);
+        assert_eq!(popped.len(), 900);
+        assert_eq!(snapshot[1050], "1050");
+    }
+
+    #[test]
+    fn shares_untouched_leaves() {
+        let vec: PersistentVec<u32> = (0..100).collect();
+        let mut other = vec.clone();
+        other.set(0, 42);
+        assert!(std::rc::Rc::ptr_eq(&vec.tail, &other.tail));
+        assert!(!std::rc::Rc::ptr_eq(&vec.root, &other.root));
+    }
+
+    #[test]
+    fn vec_conversions() {
+        let vec: Vec<u32> = (0..70).collect();
+        let persistent = PersistentVec::from(vec.clone());
+        assert_eq!(persistent.len(), 70);
+        assert_eq!(Vec::from(&persistent), vec);
+        assert_eq!(persistent.to_vec(), vec);
+        assert_eq!(persistent, persistent.iter().copied().collect());
+    }
+}
@@ -0,0 +1,29 @@
+use std::ops::{Deref, DerefMut};
+
+// Keeps the value on its own cache line(s) so that atomics written by
+// different threads don't invalidate each other's lines. 128 bytes covers
+// the adjacent-line prefetching of recent x86 parts and the line size of
+// Apple's ARM cores.
+#[derive(Debug, Default)]
+#[repr(align(128))]
+pub struct CachePadded<T>(T);
+
+impl<T> CachePadded<T> {
+    pub fn new(value: T) -> CachePadded<T> {
+        CachePadded(value)
+    }
+}
+
+impl<T> Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
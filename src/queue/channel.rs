@@ -0,0 +1,327 @@
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+use super::{BoundedQueue, BoundedQueueError};
+
+struct Shared<T> {
+    queue: BoundedQueue<T>,
+    senders: AtomicUsize,
+    receivers: AtomicUsize,
+}
+
+// Failed sends hand the item back, so nothing is lost when the other side is gone.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SendError<T>(pub T);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrySendError<T> {
+    Full(T),
+    Disconnected(T),
+}
+
+// every sender is gone and the channel has been drained
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RecvError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TryRecvError {
+    Empty,
+    Disconnected,
+}
+
+// A channel over a `BoundedQueue` shared by any number of senders and
+// receivers. The queue is closed when the last handle on either side is
+// dropped: receivers then drain what's left before seeing `RecvError`,
+// and senders get their items back.
+pub fn bounded_channel<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
+    let shared = Arc::new(Shared {
+        queue: BoundedQueue::new(capacity),
+        senders: AtomicUsize::new(1),
+        receivers: AtomicUsize::new(1),
+    });
+    let sender = Sender {
+        shared: shared.clone(),
+    };
+    (sender, Receiver { shared })
+}
+
+pub struct Sender<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Sender<T> {
+    pub fn try_send(&self, item: T) -> Result<(), TrySendError<T>> {
+        self.shared.queue.push(item).map_err(|error| match error {
+            (BoundedQueueError::Full, item) => TrySendError::Full(item),
+            (_, item) => TrySendError::Disconnected(item),
+        })
+    }
+
+    // waits for room, failing only once every receiver is gone
+    pub fn send(&self, item: T) -> Result<(), SendError<T>> {
+        self.shared
+            .queue
+            .enqueue_until(item, None)
+            .map_err(|(_, item)| SendError(item))
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.queue.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shared.queue.is_empty()
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.queue.is_closed()
+    }
+}
+
+impl<T> Clone for Sender<T> {
+    fn clone(&self) -> Sender<T> {
+        self.shared.senders.fetch_add(1, Ordering::Relaxed);
+        Sender {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Sender<T> {
+    fn drop(&mut self) {
+        if self.shared.senders.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.queue.close();
+        }
+    }
+}
+
+pub struct Receiver<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> Receiver<T> {
+    pub fn try_recv(&self) -> Result<T, TryRecvError> {
+        // checked first: disconnected and then found empty means drained for good
+        let disconnected = self.shared.queue.is_closed();
+        match self.shared.queue.dequeue() {
+            Some(item) => Ok(item),
+            None if disconnected => Err(TryRecvError::Disconnected),
+            None => Err(TryRecvError::Empty),
+        }
+    }
+
+    // waits for an item, failing only once every sender is gone and the
+    // channel is drained
+    pub fn recv(&self) -> Result<T, RecvError> {
+        self.shared.queue.dequeue_blocking().map_err(|_| RecvError)
+    }
+
+    // blocks for each item, ending once the channel is disconnected and drained
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { receiver: self }
+    }
+
+    // only what is queued right now, never blocks
+    pub fn try_iter(&self) -> TryIter<'_, T> {
+        TryIter { receiver: self }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.shared.queue.capacity()
+    }
+
+    pub fn len(&self) -> usize {
+        self.shared.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shared.queue.is_empty()
+    }
+
+    pub fn is_disconnected(&self) -> bool {
+        self.shared.queue.is_closed()
+    }
+}
+
+impl<T> Clone for Receiver<T> {
+    fn clone(&self) -> Receiver<T> {
+        self.shared.receivers.fetch_add(1, Ordering::Relaxed);
+        Receiver {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<T> Drop for Receiver<T> {
+    fn drop(&mut self) {
+        if self.shared.receivers.fetch_sub(1, Ordering::AcqRel) == 1 {
+            self.shared.queue.close();
+        }
+    }
+}
+
+pub struct Iter<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<T> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+pub struct TryIter<'a, T> {
+    receiver: &'a Receiver<T>,
+}
+
+impl<T> Iterator for TryIter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+pub struct IntoIter<T> {
+    receiver: Receiver<T>,
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.receiver.recv().ok()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Receiver<T> {
+    type Item = T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T> IntoIterator for Receiver<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter { receiver: self }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::{bounded_channel, RecvError, SendError, TryRecvError, TrySendError};
+    use crate::vec::Vec;
+
+    #[test]
+    fn send_recv() {
+        let (tx, rx) = bounded_channel(2);
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+        tx.send(1).unwrap();
+        tx.try_send(2).unwrap();
+        assert_eq!(tx.try_send(3), Err(TrySendError::Full(3)));
+        assert_eq!(rx.len(), 2);
+
+        assert_eq!(rx.recv(), Ok(1));
+        assert_eq!(rx.try_recv(), Ok(2));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Empty));
+    }
+
+    #[test]
+    fn last_sender_disconnects() {
+        let (tx, rx) = bounded_channel(4);
+        let other = tx.clone();
+        tx.send("a").unwrap();
+        drop(tx);
+        assert!(!rx.is_disconnected());
+
+        other.send("b").unwrap();
+        drop(other);
+        assert!(rx.is_disconnected());
+        assert_eq!(rx.recv(), Ok("a"));
+        assert_eq!(rx.try_recv(), Ok("b"));
+        assert_eq!(rx.recv(), Err(RecvError));
+        assert_eq!(rx.try_recv(), Err(TryRecvError::Disconnected));
+    }
+
+    #[test]
+    fn last_receiver_disconnects() {
+        let (tx, rx) = bounded_channel(1);
+        let other = rx.clone();
+        drop(rx);
+        tx.send(String::from("kept")).unwrap();
+        drop(other);
+
+        assert_eq!(
+            tx.try_send(String::from("a")),
+            Err(TrySendError::Disconnected(String::from("a")))
+        );
+        assert_eq!(
+            tx.send(String::from("b")),
+            Err(SendError(String::from("b")))
+        );
+    }
+
+    #[test]
+    fn blocked_receiver_sees_disconnect() {
+        let (tx, rx) = bounded_channel::<u32>(1);
+        let waiting = thread::spawn(move || rx.recv());
+        thread::sleep(Duration::from_millis(20));
+        drop(tx);
+        assert_eq!(waiting.join().unwrap(), Err(RecvError));
+    }
+
+    #[test]
+    fn iter_ends_on_disconnect() {
+        const ITEMS: u64 = 1000;
+        let (tx, rx) = bounded_channel(8);
+
+        let producers: Vec<_> = (0..4)
+            .map(|_| {
+                let tx = tx.clone();
+                thread::spawn(move || (0..ITEMS).for_each(|n| tx.send(n).unwrap()))
+            })
+            .collect();
+        drop(tx);
+
+        let consumers: Vec<_> = (0..3)
+            .map(|_| {
+                let rx = rx.clone();
+                thread::spawn(move || rx.into_iter().sum::<u64>())
+            })
+            .collect();
+        let mut total: u64 = rx.iter().sum();
+
+        producers
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+        total += consumers
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum::<u64>();
+        assert_eq!(total, 4 * ITEMS * (ITEMS - 1) / 2);
+    }
+
+    #[test]
+    fn try_iter() {
+        let (tx, rx) = bounded_channel(4);
+        (0..3).for_each(|n| tx.send(n).unwrap());
+        assert!(rx.try_iter().eq(0..3));
+        assert_eq!(rx.try_iter().next(), None);
+        assert!(!rx.is_disconnected());
+    }
+}
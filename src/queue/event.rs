@@ -0,0 +1,117 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Condvar, Mutex,
+    },
+    task::Waker,
+    time::Instant,
+};
+
+use crate::{
+    slot_map::{Key, SlotMap},
+    vec::Vec,
+};
+
+// Lets threads sleep until some condition, checked by `attempt`, can be met.
+//
+// A waiter registers itself and retries under the mutex before every wait;
+// a notifier changes the state first and then takes the same mutex before
+// signalling. So either the retry sees the change or the signal arrives
+// after the waiter is asleep, never in between. Notifiers skip the mutex
+// while nobody waits: the waiter count and the queue state are both accessed
+// with sequentially consistent operations, so a notifier that misses the
+// registration is ordered before the waiter's retry, which then succeeds.
+//
+// Tasks take part the same way with a `Waker` instead of the condvar: they
+// register, retry, and only then return `Pending`. A notification takes all
+// registered wakers, so a task has to register again every time it waits.
+pub struct Event {
+    wakers: Mutex<SlotMap<Waker>>,
+    condvar: Condvar,
+    waiters: AtomicUsize,
+}
+
+impl Event {
+    pub fn new() -> Event {
+        Event {
+            wakers: Mutex::new(SlotMap::new()),
+            condvar: Condvar::new(),
+            waiters: AtomicUsize::new(0),
+        }
+    }
+
+    // Wakes all waiters. Waking just one could hand the signal to a waiter
+    // that is timing out, stranding the others.
+    pub fn notify(&self) {
+        if self.waiters.load(Ordering::SeqCst) > 0 {
+            // removed one by one rather than swapped for an empty map, so the
+            // generations advance and the keys the woken tasks still hold go
+            // stale instead of naming whoever registers next
+            let woken: Vec<Waker> = {
+                let mut wakers = self.wakers.lock().unwrap();
+                let keys: Vec<Key> = wakers.keys().collect();
+                keys.into_iter()
+                    .filter_map(|key| wakers.remove(key))
+                    .collect()
+            };
+            self.waiters.fetch_sub(woken.len(), Ordering::SeqCst);
+            self.condvar.notify_all();
+            woken.into_iter().for_each(Waker::wake);
+        }
+    }
+
+    // Registers `waker`, or replaces the one registered under `key` if that
+    // hasn't been woken yet. The caller must retry before it returns `Pending`.
+    pub fn register(&self, key: &mut Option<Key>, waker: &Waker) {
+        let mut wakers = self.wakers.lock().unwrap();
+        if let Some(registered) = key.and_then(|key| wakers.get_mut(key)) {
+            if !registered.will_wake(waker) {
+                registered.clone_from(waker);
+            }
+            return;
+        }
+        *key = Some(wakers.insert(waker.clone()));
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+    }
+
+    // Withdraws a registration, e.g. when its future completes or is dropped.
+    // Every registered waker is woken by a notification, so there is no
+    // wakeup to pass on: dropping a woken task can't leave another one asleep.
+    pub fn unregister(&self, key: &mut Option<Key>) {
+        if let Some(key) = key.take() {
+            if self.wakers.lock().unwrap().remove(key).is_some() {
+                self.waiters.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+    }
+
+    // Calls `attempt` until it succeeds or the deadline passes. `attempt` runs
+    // with the mutex held, so it must not notify any `Event` itself; callers
+    // do that after this returns.
+    pub fn wait_until<R, F>(&self, deadline: Option<Instant>, mut attempt: F) -> Option<R>
+    where
+        F: FnMut() -> Option<R>,
+    {
+        let mut guard = self.wakers.lock().unwrap();
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+
+        let result = loop {
+            if let Some(result) = attempt() {
+                break Some(result);
+            }
+            match deadline {
+                None => guard = self.condvar.wait(guard).unwrap(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break None;
+                    }
+                    guard = self.condvar.wait_timeout(guard, deadline - now).unwrap().0;
+                }
+            }
+        };
+
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+        result
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use super::{BoundedQueue, BoundedQueueError};
+use crate::slot_map::Key;
+
+impl<T> BoundedQueue<T> {
+    // Resolves once the item is in, or to `Closed` (dropping the item). Both
+    // futures are cancel-safe: dropping one before it resolves withdraws its
+    // waker and leaves the queue as it was.
+    pub fn send_async(&self, item: T) -> SendFuture<'_, T> {
+        SendFuture {
+            queue: self,
+            item: Some(item),
+            key: None,
+        }
+    }
+
+    // resolves to `Closed` only once the queue is closed and empty
+    pub fn recv_async(&self) -> RecvFuture<'_, T> {
+        RecvFuture {
+            queue: self,
+            key: None,
+        }
+    }
+}
+
+pub struct SendFuture<'a, T> {
+    queue: &'a BoundedQueue<T>,
+    item: Option<T>,
+    // registration on the queue's `read_event` while waiting for room
+    key: Option<Key>,
+}
+
+// the item is only ever moved, never pinned
+impl<T> Unpin for SendFuture<'_, T> {}
+
+impl<T> SendFuture<'_, T> {
+    fn attempt(&mut self) -> Option<Result<(), BoundedQueueError>> {
+        let item = self.item.take().expect("polled after completion");
+        match self.queue.push(item) {
+            Ok(()) => Some(Ok(())),
+            Err((BoundedQueueError::Full, item)) => {
+                self.item = Some(item);
+                None
+            }
+            Err((error, _)) => Some(Err(error)),
+        }
+    }
+}
+
+impl<T> Future for SendFuture<'_, T> {
+    type Output = Result<(), BoundedQueueError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let result = this.attempt().or_else(|| {
+            this.queue.read_event.register(&mut this.key, cx.waker());
+            this.attempt()
+        });
+
+        match result {
+            Some(result) => {
+                this.queue.read_event.unregister(&mut this.key);
+                Poll::Ready(result)
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Drop for SendFuture<'_, T> {
+    fn drop(&mut self) {
+        self.queue.read_event.unregister(&mut self.key);
+    }
+}
+
+pub struct RecvFuture<'a, T> {
+    queue: &'a BoundedQueue<T>,
+    // registration on the queue's `write_event` while waiting for an item
+    key: Option<Key>,
+}
+
+impl<T> RecvFuture<'_, T> {
+    fn attempt(&self) -> Option<Result<T, BoundedQueueError>> {
+        // checked first: closed and then found empty means drained for good
+        let closed = self.queue.is_closed();
+        match self.queue.dequeue() {
+            Some(item) => Some(Ok(item)),
+            None if closed => Some(Err(BoundedQueueError::Closed)),
+            None => None,
+        }
+    }
+}
+
+impl<T> Future for RecvFuture<'_, T> {
+    type Output = Result<T, BoundedQueueError>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let result = this.attempt().or_else(|| {
+            this.queue.write_event.register(&mut this.key, cx.waker());
+            this.attempt()
+        });
+
+        match result {
+            Some(result) => {
+                this.queue.write_event.unregister(&mut this.key);
+                Poll::Ready(result)
+            }
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<T> Drop for RecvFuture<'_, T> {
+    fn drop(&mut self) {
+        self.queue.write_event.unregister(&mut self.key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        future::Future,
+        pin::Pin,
+        rc::Rc,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::{Context, Poll, Wake, Waker},
+        thread,
+        time::Duration,
+    };
+
+    use crate::{
+        executor::{block_on, Executor},
+        queue::{BoundedQueue, BoundedQueueError},
+    };
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn counting_waker() -> (Arc<CountingWaker>, Waker) {
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        (counter.clone(), Waker::from(counter))
+    }
+
+    fn poll<F: Future + Unpin>(future: &mut F, waker: &Waker) -> Poll<F::Output> {
+        Pin::new(future).poll(&mut Context::from_waker(waker))
+    }
+
+    #[test]
+    fn tasks_take_turns() {
+        const ITEMS: u32 = 100;
+        let queue = Rc::new(BoundedQueue::new(1));
+        let sum = Rc::new(Cell::new(0));
+        let mut executor = Executor::new();
+
+        let rx = queue.clone();
+        let total = sum.clone();
+        executor.spawn(async move {
+            while let Ok(item) = rx.recv_async().await {
+                total.set(total.get() + item);
+            }
+        });
+        let tx = queue.clone();
+        executor.spawn(async move {
+            for n in 0..ITEMS {
+                tx.send_async(n).await.unwrap();
+            }
+            tx.close();
+        });
+        executor.run();
+
+        assert_eq!(sum.get(), ITEMS * (ITEMS - 1) / 2);
+        assert_eq!(
+            block_on(queue.send_async(0)),
+            Err(BoundedQueueError::Closed)
+        );
+    }
+
+    #[test]
+    fn woken_from_other_threads() {
+        const ITEMS: u64 = 10_000;
+        let queue: Arc<BoundedQueue<u64>> = Arc::new(BoundedQueue::new(4));
+        let producer = {
+            let queue = queue.clone();
+            thread::spawn(move || (0..ITEMS).for_each(|n| queue.enqueue_blocking(n).unwrap()))
+        };
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                block_on(async {
+                    let mut sum = 0;
+                    for _ in 0..ITEMS {
+                        sum += queue.recv_async().await.unwrap();
+                    }
+                    sum
+                })
+            })
+        };
+
+        producer.join().unwrap();
+        assert_eq!(consumer.join().unwrap(), ITEMS * (ITEMS - 1) / 2);
+
+        let waiting = {
+            let queue = queue.clone();
+            thread::spawn(move || block_on(queue.recv_async()))
+        };
+        thread::sleep(Duration::from_millis(20));
+        queue.close();
+        assert_eq!(waiting.join().unwrap(), Err(BoundedQueueError::Closed));
+    }
+
+    #[test]
+    fn dropped_futures_withdraw() {
+        let queue = BoundedQueue::new(1);
+        let (first, first_waker) = counting_waker();
+        let (second, second_waker) = counting_waker();
+
+        let mut cancelled = queue.recv_async();
+        assert!(poll(&mut cancelled, &first_waker).is_pending());
+        drop(cancelled);
+        let mut waiting = queue.recv_async();
+        assert!(poll(&mut waiting, &second_waker).is_pending());
+
+        queue.enqueue(1).unwrap();
+        assert_eq!(first.0.load(Ordering::SeqCst), 0);
+        assert_eq!(second.0.load(Ordering::SeqCst), 1);
+        assert_eq!(poll(&mut waiting, &second_waker), Poll::Ready(Ok(1)));
+
+        // a sender cancelled while waiting for room leaves its item out
+        queue.enqueue(2).unwrap();
+        let mut send = queue.send_async(3);
+        assert!(poll(&mut send, &first_waker).is_pending());
+        drop(send);
+        assert_eq!(queue.dequeue(), Some(2));
+        assert_eq!(queue.dequeue(), None);
+        assert_eq!(first.0.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn dropping_a_woken_future_loses_no_wakeup() {
+        let queue = BoundedQueue::new(1);
+        let (first, first_waker) = counting_waker();
+        let (second, second_waker) = counting_waker();
+
+        let mut woken = queue.recv_async();
+        let mut other = queue.recv_async();
+        assert!(poll(&mut woken, &first_waker).is_pending());
+        assert!(poll(&mut other, &second_waker).is_pending());
+
+        queue.enqueue(5).unwrap();
+        drop(woken);
+        assert_eq!(first.0.load(Ordering::SeqCst), 1);
+        assert_eq!(second.0.load(Ordering::SeqCst), 1);
+        assert_eq!(poll(&mut other, &second_waker), Poll::Ready(Ok(5)));
+    }
+
+    #[test]
+    fn woken_keys_are_not_reused() {
+        let queue = BoundedQueue::new(1);
+        let (first, first_waker) = counting_waker();
+        let (second, second_waker) = counting_waker();
+
+        let mut woken = queue.recv_async();
+        assert!(poll(&mut woken, &first_waker).is_pending());
+        queue.enqueue(1).unwrap();
+        assert_eq!(queue.dequeue(), Some(1));
+
+        // registers after the notification, then the woken future re-registers
+        let mut later = queue.recv_async();
+        assert!(poll(&mut later, &second_waker).is_pending());
+        assert!(poll(&mut woken, &first_waker).is_pending());
+
+        queue.enqueue(2).unwrap();
+        assert_eq!(first.0.load(Ordering::SeqCst), 2);
+        assert_eq!(second.0.load(Ordering::SeqCst), 1);
+    }
+}
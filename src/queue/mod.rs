@@ -1,126 +1,596 @@
 #![allow(dead_code)]
 
 use std::{
-    alloc,
+    cell::UnsafeCell,
     fmt::Debug,
-    sync::{atomic, atomic::AtomicUsize, Condvar, Mutex, RwLock},
+    hint,
+    mem::MaybeUninit,
+    sync::atomic::{self, AtomicBool, AtomicUsize},
+    thread,
+    time::{Duration, Instant},
 };
 
-#[derive(Debug, PartialEq)]
+mod cache_padded;
+pub mod channel;
+mod event;
+pub mod future;
+pub mod select;
+pub mod spsc;
+
+use crate::{
+    vec::{growth::GrowthPolicy, Vec},
+    vec_deque::VecDeque,
+};
+use cache_padded::CachePadded;
+use event::Event;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BoundedQueueError {
     Full,
+    // `close` was called, and for reads all items left have been taken
+    Closed,
+    // a timed operation hit its deadline
+    Timeout,
+}
+
+// What `enqueue` and `enqueue_overflowing` do when the queue is full. No
+// other write looks at it, and none of them count towards `dropped_count`:
+// - the blocking and timed writes, `send_async` and `Select::send` wait for
+//   room instead;
+// - `enqueue_many` and `enqueue_iter` stop at the first item that doesn't fit;
+// - a `bounded_channel` queue always uses `Reject`, so `try_send` fails with
+//   `Full`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    // fail with `Full`
+    Reject,
+    // discard the new item
+    DropNewest,
+    // discard the oldest item to make room
+    OverwriteOldest,
+}
+
+struct Slot<T> {
+    // see `BoundedQueue`
+    stamp: AtomicUsize,
+    item: UnsafeCell<MaybeUninit<T>>,
 }
 
+// A lock-free multi-producer multi-consumer queue after Dmitry Vyukov's
+// bounded queue. `front` (the next write) and `rear` (the next read) are
+// stamps: a slot index in the low bits and a lap count above them, a lap
+// being the next power of two above the capacity so the index can never
+// carry into the lap.
+//
+// Every slot carries a stamp too. It equals the writer's stamp when the slot
+// is free for that lap and the reader's stamp plus one once the item is in,
+// then the reader bumps it a lap ahead. So a thread that wins the CAS on
+// `front`/`rear` owns its slot outright, and a stamp lagging by a lap tells
+// it the queue is full (or empty).
 pub struct BoundedQueue<T> {
-    front: AtomicUsize,
-    rear: AtomicUsize,
+    front: CachePadded<AtomicUsize>,
+    rear: CachePadded<AtomicUsize>,
+    one_lap: usize,
+    closed: AtomicBool,
+    policy: OverflowPolicy,
+    // items discarded by the overflow policy
+    dropped: AtomicUsize,
 
-    write_event: (Mutex<()>, Condvar),
-    read_event: (Mutex<()>, Condvar),
+    // signalled after an item is written, and after one is read
+    write_event: Event,
+    read_event: Event,
 
-    buffer: RwLock<Box<[T]>>,
+    buffer: Box<[Slot<T>]>,
 }
 
-unsafe impl<T> Send for BoundedQueue<T> {}
-unsafe impl<T> Sync for BoundedQueue<T> {}
+// items are moved between threads but never shared, so `T: Sync` isn't needed
+unsafe impl<T: Send> Send for BoundedQueue<T> {}
+unsafe impl<T: Send> Sync for BoundedQueue<T> {}
 
-impl<T: Copy> BoundedQueue<T> {
+impl<T> BoundedQueue<T> {
     pub fn new(size: usize) -> BoundedQueue<T> {
-        let buffer_layout = alloc::Layout::array::<T>(size + 1).unwrap();
-        let boxed_buffer: Box<[T]>;
-        unsafe {
-            let ptr = alloc::alloc(buffer_layout) as *mut T;
-            let buffer = core::slice::from_raw_parts_mut(ptr, size + 1);
-            boxed_buffer = Box::from_raw(buffer);
-        }
+        BoundedQueue::with_overflow_policy(size, OverflowPolicy::Reject)
+    }
+
+    pub fn with_overflow_policy(size: usize, policy: OverflowPolicy) -> BoundedQueue<T> {
+        assert!(size > 0, "size must be positive");
+
         BoundedQueue {
-            front: AtomicUsize::new(0),
-            rear: AtomicUsize::new(0),
-            read_event: (Mutex::new(()), Condvar::new()),
-            write_event: (Mutex::new(()), Condvar::new()),
-            buffer: RwLock::new(boxed_buffer),
+            front: CachePadded::new(AtomicUsize::new(0)),
+            rear: CachePadded::new(AtomicUsize::new(0)),
+            one_lap: (size + 1).next_power_of_two(),
+            closed: AtomicBool::new(false),
+            policy,
+            dropped: AtomicUsize::new(0),
+            read_event: Event::new(),
+            write_event: Event::new(),
+            buffer: (0..size)
+                .map(|index| Slot {
+                    stamp: AtomicUsize::new(index),
+                    item: UnsafeCell::new(MaybeUninit::uninit()),
+                })
+                .collect(),
         }
     }
 
+    pub fn capacity(&self) -> usize {
+        self.buffer.len()
+    }
+
+    pub fn overflow_policy(&self) -> OverflowPolicy {
+        self.policy
+    }
+
+    // how many items the overflow policy has discarded so far
+    pub fn dropped_count(&self) -> usize {
+        self.dropped.load(atomic::Ordering::Relaxed)
+    }
+
+    pub fn len(&self) -> usize {
+        loop {
+            let front = self.front.load(atomic::Ordering::SeqCst);
+            let rear = self.rear.load(atomic::Ordering::SeqCst);
+            // only trust a consistent snapshot of both stamps
+            if self.front.load(atomic::Ordering::SeqCst) == front {
+                return self.distance(rear, front);
+            }
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    // Stops all writes; reads go on until the queue is empty and then fail
+    // with `Closed` instead of waiting. Returns whether this call closed it.
+    pub fn close(&self) -> bool {
+        let closed = !self.closed.swap(true, atomic::Ordering::SeqCst);
+        if closed {
+            self.write_event.notify();
+            self.read_event.notify();
+        }
+        closed
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(atomic::Ordering::SeqCst)
+    }
+
+    // A full queue is handled by the overflow policy; the item is dropped if
+    // it is rejected or the queue is closed.
     pub fn enqueue(&self, item: T) -> Result<(), BoundedQueueError> {
-        let mut buffer = self.buffer.write().unwrap();
-        let front = self.front.load(atomic::Ordering::Relaxed);
-        let rear = self.rear.load(atomic::Ordering::Relaxed);
+        self.enqueue_overflowing(item).map(drop)
+    }
 
-        if (front + 1) % buffer.len() == rear {
-            Err(BoundedQueueError::Full)
-        } else {
-            buffer[front] = item;
-            self.front
-                .store((front + 1) % buffer.len(), atomic::Ordering::Relaxed);
-            self.notify_write();
-            Ok(())
+    // Like `enqueue`, but hands over the item the overflow policy discarded:
+    // the new one, or the oldest one queued. If other producers keep taking
+    // the room made by `OverwriteOldest`, it evicts again; only the last
+    // eviction is handed over, the earlier ones are dropped and just show in
+    // `dropped_count`.
+    pub fn enqueue_overflowing(&self, item: T) -> Result<Option<T>, BoundedQueueError> {
+        let item = match self.push(item) {
+            Ok(()) => return Ok(None),
+            Err((BoundedQueueError::Full, item)) => item,
+            Err((error, _)) => return Err(error),
+        };
+
+        let dropped = match self.policy {
+            OverflowPolicy::Reject => return Err(BoundedQueueError::Full),
+            OverflowPolicy::DropNewest => item,
+            OverflowPolicy::OverwriteOldest => {
+                let mut item = item;
+                let mut evicted = None;
+                // other producers may take the room we make, so this can
+                // take more than one eviction
+                loop {
+                    if let Some(oldest) = self.try_pop() {
+                        if evicted.replace(oldest).is_some() {
+                            self.dropped.fetch_add(1, atomic::Ordering::Relaxed);
+                        }
+                    }
+                    match self.try_push(item) {
+                        Ok(()) => break,
+                        Err(back) => item = back,
+                    }
+                }
+                self.write_event.notify();
+                match evicted {
+                    Some(oldest) => oldest,
+                    // consumers made room in the meantime
+                    None => return Ok(None),
+                }
+            }
+        };
+        self.dropped.fetch_add(1, atomic::Ordering::Relaxed);
+        Ok(Some(dropped))
+    }
+
+    // hands the item back on failure, so it can be retried
+    fn push(&self, item: T) -> Result<(), (BoundedQueueError, T)> {
+        if self.is_closed() {
+            return Err((BoundedQueueError::Closed, item));
+        }
+        self.try_push(item)
+            .map_err(|item| (BoundedQueueError::Full, item))?;
+        self.write_event.notify();
+        Ok(())
+    }
+
+    fn try_push(&self, item: T) -> Result<(), T> {
+        let mut front = self.front.load(atomic::Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[front & (self.one_lap - 1)];
+            let stamp = slot.stamp.load(atomic::Ordering::Acquire);
+
+            if stamp == front {
+                match self.front.compare_exchange_weak(
+                    front,
+                    self.next_stamp(front),
+                    atomic::Ordering::SeqCst,
+                    atomic::Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        unsafe { (*slot.item.get()).write(item) };
+                        slot.stamp
+                            .store(front.wrapping_add(1), atomic::Ordering::Release);
+                        return Ok(());
+                    }
+                    Err(current) => {
+                        front = current;
+                        hint::spin_loop();
+                    }
+                }
+            } else if stamp.wrapping_add(self.one_lap) == front.wrapping_add(1) {
+                // the slot still holds last lap's item, full unless it was
+                // taken since
+                atomic::fence(atomic::Ordering::SeqCst);
+                let rear = self.rear.load(atomic::Ordering::Relaxed);
+                if rear.wrapping_add(self.one_lap) == front {
+                    return Err(item);
+                }
+                front = self.front.load(atomic::Ordering::Relaxed);
+            } else {
+                // another producer claimed the slot and is still writing it
+                thread::yield_now();
+                front = self.front.load(atomic::Ordering::Relaxed);
+            }
         }
     }
 
     pub fn dequeue(&self) -> Option<T> {
-        let buffer = self.buffer.read().unwrap();
-        let front = self.front.load(atomic::Ordering::Relaxed);
-        let rear = self.rear.load(atomic::Ordering::Relaxed);
+        let item = self.try_pop()?;
+        self.read_event.notify();
+        Some(item)
+    }
+
+    fn try_pop(&self) -> Option<T> {
+        let mut rear = self.rear.load(atomic::Ordering::Relaxed);
+        loop {
+            let slot = &self.buffer[rear & (self.one_lap - 1)];
+            let stamp = slot.stamp.load(atomic::Ordering::Acquire);
+
+            if stamp == rear.wrapping_add(1) {
+                match self.rear.compare_exchange_weak(
+                    rear,
+                    self.next_stamp(rear),
+                    atomic::Ordering::SeqCst,
+                    atomic::Ordering::Relaxed,
+                ) {
+                    Ok(_) => {
+                        let item = unsafe { (*slot.item.get()).assume_init_read() };
+                        slot.stamp
+                            .store(rear.wrapping_add(self.one_lap), atomic::Ordering::Release);
+                        return Some(item);
+                    }
+                    Err(current) => {
+                        rear = current;
+                        hint::spin_loop();
+                    }
+                }
+            } else if stamp == rear {
+                // the slot hasn't been written this lap, empty unless a
+                // producer got past it since
+                atomic::fence(atomic::Ordering::SeqCst);
+                let front = self.front.load(atomic::Ordering::Relaxed);
+                if front == rear {
+                    return None;
+                }
+                rear = self.rear.load(atomic::Ordering::Relaxed);
+            } else {
+                // another consumer claimed the slot and is still reading it
+                thread::yield_now();
+                rear = self.rear.load(atomic::Ordering::Relaxed);
+            }
+        }
+    }
 
-        if rear == front {
-            None
+    // the stamp following `stamp`, moving on to the next lap after the last slot
+    fn next_stamp(&self, stamp: usize) -> usize {
+        let index = stamp & (self.one_lap - 1);
+        if index + 1 < self.capacity() {
+            stamp + 1
         } else {
-            let item = buffer[rear].clone();
-            self.rear
-                .store((rear + 1) % buffer.len(), atomic::Ordering::Relaxed);
-            self.notify_read();
-            Some(item)
+            (stamp & !(self.one_lap - 1)).wrapping_add(self.one_lap)
         }
     }
 
-    fn notify_read(&self) {
-        self.read_event.1.notify_one();
+    // how many items lie between the `rear` and `front` stamps
+    fn distance(&self, rear: usize, front: usize) -> usize {
+        let rear_index = rear & (self.one_lap - 1);
+        let front_index = front & (self.one_lap - 1);
+        if rear_index < front_index {
+            front_index - rear_index
+        } else if rear_index > front_index {
+            self.capacity() - rear_index + front_index
+        } else if front == rear {
+            0
+        } else {
+            self.capacity()
+        }
+    }
+
+    // the item is dropped if the queue is closed
+    pub fn enqueue_blocking(&self, item: T) -> Result<(), BoundedQueueError> {
+        self.enqueue_until(item, None).map_err(|(error, _)| error)
+    }
+
+    // the item is dropped if there's still no room by the deadline; a timeout
+    // too far out to be represented waits without one
+    pub fn enqueue_timeout(&self, item: T, timeout: Duration) -> Result<(), BoundedQueueError> {
+        self.enqueue_until(item, Instant::now().checked_add(timeout))
+            .map_err(|(error, _)| error)
     }
 
-    fn notify_write(&self) {
-        self.write_event.1.notify_one();
+    pub fn enqueue_deadline(&self, item: T, deadline: Instant) -> Result<(), BoundedQueueError> {
+        self.enqueue_until(item, Some(deadline))
+            .map_err(|(error, _)| error)
     }
 
-    fn wait_read(&self) {
-        let read = self.read_event.0.lock().unwrap();
-        let _guard = self.read_event.1.wait(read).unwrap();
+    // fails only once the queue is closed and empty
+    pub fn dequeue_blocking(&self) -> Result<T, BoundedQueueError> {
+        self.dequeue_until(None)
     }
 
-    fn wait_write(&self) {
-        let written = self.write_event.0.lock().unwrap();
-        let _guard = self.write_event.1.wait(written).unwrap();
+    pub fn dequeue_timeout(&self, timeout: Duration) -> Result<T, BoundedQueueError> {
+        self.dequeue_until(Instant::now().checked_add(timeout))
     }
 
-    pub fn enqueue_blocking(&self, item: T) {
-        match self.enqueue(item) {
-            Ok(_) => (),
-            Err(BoundedQueueError::Full) => {
-                self.wait_read();
-                self.enqueue_blocking(item);
+    pub fn dequeue_deadline(&self, deadline: Instant) -> Result<T, BoundedQueueError> {
+        self.dequeue_until(Some(deadline))
+    }
+
+    fn enqueue_until(
+        &self,
+        item: T,
+        deadline: Option<Instant>,
+    ) -> Result<(), (BoundedQueueError, T)> {
+        let mut item = match self.push(item) {
+            Err((BoundedQueueError::Full, item)) => Some(item),
+            result => return result,
+        };
+
+        let pushed = self.read_event.wait_until(deadline, || {
+            if self.is_closed() {
+                return Some(Err(BoundedQueueError::Closed));
+            }
+            match self.try_push(item.take().unwrap()) {
+                Ok(()) => Some(Ok(())),
+                Err(back) => {
+                    item = Some(back);
+                    None
+                }
+            }
+        });
+        match pushed {
+            Some(Ok(())) => {
+                self.write_event.notify();
+                Ok(())
+            }
+            Some(Err(error)) => Err((error, item.unwrap())),
+            None => Err((BoundedQueueError::Timeout, item.unwrap())),
+        }
+    }
+
+    fn dequeue_until(&self, deadline: Option<Instant>) -> Result<T, BoundedQueueError> {
+        let attempt = || {
+            // checked first: closed and then found empty means drained for good
+            let closed = self.is_closed();
+            match self.try_pop() {
+                Some(item) => Some(Ok(item)),
+                None if closed => Some(Err(BoundedQueueError::Closed)),
+                None => None,
+            }
+        };
+
+        let result = match attempt() {
+            Some(result) => result,
+            None => self
+                .write_event
+                .wait_until(deadline, attempt)
+                .unwrap_or(Err(BoundedQueueError::Timeout)),
+        };
+        if result.is_ok() {
+            self.read_event.notify();
+        }
+        result
+    }
+
+    // Takes as many leading items as fit, returning how many. Runs of free
+    // slots are claimed with a single CAS and readers are woken once.
+    pub fn enqueue_many(&self, items: &[T]) -> usize
+    where
+        T: Clone,
+    {
+        if self.is_closed() {
+            return 0;
+        }
+
+        // Cloned ahead of claiming any slots: a claimed slot has to be
+        // written, or readers wait on it forever, so a `clone` that panics
+        // must not run in between.
+        let batch = items.len().min(self.capacity());
+        let mut cloned = VecDeque::with_capacity(batch);
+        let mut written = 0;
+        while written < items.len() {
+            while cloned.len() < batch.min(items.len() - written) {
+                cloned.push_back(items[written + cloned.len()].clone());
+            }
+
+            let (start, len) = self.claim_run(&self.front, cloned.len(), |pos| pos);
+            if len == 0 {
+                // tells a full queue apart from a slot another producer is
+                // still writing
+                match self.try_push(cloned.pop_front().unwrap()) {
+                    Ok(()) => written += 1,
+                    Err(_) => break,
+                }
+                continue;
+            }
+
+            let mut pos = start;
+            for item in (0..len).map(|_| cloned.pop_front().unwrap()) {
+                let slot = self.slot(pos);
+                unsafe { (*slot.item.get()).write(item) };
+                slot.stamp
+                    .store(pos.wrapping_add(1), atomic::Ordering::Release);
+                pos = self.next_stamp(pos);
+            }
+            written += len;
+        }
+
+        if written > 0 {
+            self.write_event.notify();
+        }
+        written
+    }
+
+    // Like `enqueue_many`, but one slot at a time as items can't be counted
+    // up front. Stops at the first item that doesn't fit and hands it back
+    // along with the count; pass `iter.by_ref()` to keep the rest.
+    pub fn enqueue_iter<I: IntoIterator<Item = T>>(&self, items: I) -> (usize, Option<T>) {
+        if self.is_closed() {
+            return (0, None);
+        }
+
+        let mut written = 0;
+        let mut rejected = None;
+        for item in items {
+            if let Err(item) = self.try_push(item) {
+                rejected = Some(item);
+                break;
             }
+            written += 1;
+        }
+
+        if written > 0 {
+            self.write_event.notify();
+        }
+        (written, rejected)
+    }
+
+    // Moves up to `max` items to the end of `out`, returning how many.
+    // Writers are woken once for the whole batch.
+    pub fn dequeue_into<G: GrowthPolicy>(&self, out: &mut Vec<T, G>, max: usize) -> usize {
+        let mut read = 0;
+        while read < max {
+            // grown before any slots are claimed, see `enqueue_many`
+            out.reserve(out.len() + (max - read).min(self.capacity()));
+            let (start, len) = self.claim_run(&self.rear, max - read, |pos| pos.wrapping_add(1));
+            if len == 0 {
+                // tells an empty queue apart from a slot still being written
+                match self.try_pop() {
+                    Some(item) => {
+                        out.push(item);
+                        read += 1;
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+
+            let mut pos = start;
+            for _ in 0..len {
+                let slot = self.slot(pos);
+                out.push(unsafe { (*slot.item.get()).assume_init_read() });
+                slot.stamp
+                    .store(pos.wrapping_add(self.one_lap), atomic::Ordering::Release);
+                pos = self.next_stamp(pos);
+            }
+            read += len;
+        }
+
+        if read > 0 {
+            self.read_event.notify();
         }
+        read
     }
 
-    pub fn dequeue_blocking(&self) -> T {
-        match self.dequeue() {
-            Some(item) => item,
-            None => {
-                self.wait_write();
-                self.dequeue_blocking()
+    // Claims the run of up to `max` consecutive slots from `cursor` whose
+    // stamps all read `stamp(pos)`, returning its first position and length.
+    // Such slots can only change hands by moving `cursor` past them, so
+    // winning the CAS makes all of them ours.
+    fn claim_run<F>(&self, cursor: &AtomicUsize, max: usize, stamp: F) -> (usize, usize)
+    where
+        F: Fn(usize) -> usize,
+    {
+        loop {
+            let start = cursor.load(atomic::Ordering::Relaxed);
+            let mut pos = start;
+            let mut len = 0;
+            while len < max && self.slot(pos).stamp.load(atomic::Ordering::Acquire) == stamp(pos) {
+                pos = self.next_stamp(pos);
+                len += 1;
+            }
+            if len == 0 {
+                return (start, 0);
+            }
+
+            match cursor.compare_exchange_weak(
+                start,
+                pos,
+                atomic::Ordering::SeqCst,
+                atomic::Ordering::Relaxed,
+            ) {
+                Ok(_) => return (start, len),
+                Err(_) => hint::spin_loop(),
             }
         }
     }
+
+    fn slot(&self, pos: usize) -> &Slot<T> {
+        &self.buffer[pos & (self.one_lap - 1)]
+    }
+}
+
+impl<T> Drop for BoundedQueue<T> {
+    fn drop(&mut self) {
+        let front = *self.front.get_mut();
+        let rear = *self.rear.get_mut();
+        let len = self.distance(rear, front);
+        let start = rear & (self.one_lap - 1);
+        for offset in 0..len {
+            let slot = &mut self.buffer[(start + offset) % self.capacity()];
+            unsafe { slot.item.get_mut().assume_init_drop() };
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use std::{
-        sync::{Arc, RwLock},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, RwLock,
+        },
         thread,
+        time::{Duration, Instant},
     };
 
-    use crate::queue::BoundedQueueError;
+    use crate::queue::{BoundedQueueError, OverflowPolicy};
 
     use super::BoundedQueue;
 
@@ -161,15 +631,15 @@ mod tests {
 
         thread::spawn(move || {
             let tx = sender.read().unwrap();
-            tx.enqueue_blocking(1);
-            tx.enqueue_blocking(2);
-            tx.enqueue_blocking(3);
+            tx.enqueue_blocking(1).unwrap();
+            tx.enqueue_blocking(2).unwrap();
+            tx.enqueue_blocking(3).unwrap();
         });
 
         let rx = receiver.read().unwrap();
-        assert_eq!(1, rx.dequeue_blocking());
-        assert_eq!(2, rx.dequeue_blocking());
-        assert_eq!(3, rx.dequeue_blocking());
+        assert_eq!(Ok(1), rx.dequeue_blocking());
+        assert_eq!(Ok(2), rx.dequeue_blocking());
+        assert_eq!(Ok(3), rx.dequeue_blocking());
     }
 
     #[test]
@@ -181,24 +651,525 @@ mod tests {
 
         thread::spawn(move || {
             let tx = sender1.read().unwrap();
-            tx.enqueue_blocking(1);
-            tx.enqueue_blocking(1);
-            tx.enqueue_blocking(1);
+            tx.enqueue_blocking(1).unwrap();
+            tx.enqueue_blocking(1).unwrap();
+            tx.enqueue_blocking(1).unwrap();
         });
 
         thread::spawn(move || {
             let tx = sender2.read().unwrap();
-            tx.enqueue_blocking(1);
-            tx.enqueue_blocking(1);
-            tx.enqueue_blocking(1);
+            tx.enqueue_blocking(1).unwrap();
+            tx.enqueue_blocking(1).unwrap();
+            tx.enqueue_blocking(1).unwrap();
         });
 
         let rx = receiver.read().unwrap();
-        assert_eq!(1, rx.dequeue_blocking());
-        assert_eq!(1, rx.dequeue_blocking());
-        assert_eq!(1, rx.dequeue_blocking());
-        assert_eq!(1, rx.dequeue_blocking());
-        assert_eq!(1, rx.dequeue_blocking());
-        assert_eq!(1, rx.dequeue_blocking());
+        assert_eq!(Ok(1), rx.dequeue_blocking());
+        assert_eq!(Ok(1), rx.dequeue_blocking());
+        assert_eq!(Ok(1), rx.dequeue_blocking());
+        assert_eq!(Ok(1), rx.dequeue_blocking());
+        assert_eq!(Ok(1), rx.dequeue_blocking());
+        assert_eq!(Ok(1), rx.dequeue_blocking());
+    }
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn owned_items() {
+        let queue: BoundedQueue<String> = BoundedQueue::new(2);
+        queue.enqueue(String::from("a")).unwrap();
+        queue.enqueue(String::from("b")).unwrap();
+        assert_eq!(queue.dequeue().as_deref(), Some("a"));
+        queue.enqueue(String::from("c")).unwrap();
+        assert_eq!(queue.dequeue().as_deref(), Some("b"));
+        assert_eq!(queue.dequeue().as_deref(), Some("c"));
+        assert_eq!(queue.dequeue(), None);
+
+        let boxes: BoundedQueue<Box<[u8]>> = BoundedQueue::new(1);
+        boxes.enqueue(Box::new([1, 2, 3])).unwrap();
+        assert_eq!(boxes.dequeue().as_deref(), Some(&[1, 2, 3][..]));
+    }
+
+    #[test]
+    fn drops_items() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let queue = BoundedQueue::new(3);
+        for _ in 0..3 {
+            queue.enqueue(DropCounter(drops.clone())).unwrap();
+        }
+
+        // rejected when full
+        assert!(queue.enqueue(DropCounter(drops.clone())).is_err());
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+        drop(queue.dequeue());
+        assert_eq!(drops.load(Ordering::Relaxed), 2);
+
+        // wrap around so the leftovers straddle the end of the buffer
+        queue.enqueue(DropCounter(drops.clone())).unwrap();
+        drop(queue);
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn spsc_owned_items() {
+        let queue: Arc<BoundedQueue<String>> = Arc::new(BoundedQueue::new(2));
+        let sender = queue.clone();
+
+        thread::spawn(move || {
+            for n in 0..100 {
+                sender.enqueue_blocking(n.to_string()).unwrap();
+            }
+        });
+
+        for n in 0..100 {
+            assert_eq!(queue.dequeue_blocking(), Ok(n.to_string()));
+        }
+    }
+
+    #[test]
+    fn len_and_wrap_around() {
+        let queue = BoundedQueue::new(3);
+        assert_eq!(queue.capacity(), 3);
+        for round in 0..10 {
+            queue.enqueue(round).unwrap();
+            queue.enqueue(round + 100).unwrap();
+            assert_eq!(queue.len(), 2);
+            queue.enqueue(round + 200).unwrap();
+            assert!(queue.is_full());
+            assert_eq!(queue.enqueue(0), Err(BoundedQueueError::Full));
+
+            assert_eq!(queue.dequeue(), Some(round));
+            assert_eq!(queue.dequeue(), Some(round + 100));
+            assert_eq!(queue.dequeue(), Some(round + 200));
+            assert!(queue.is_empty());
+        }
+    }
+
+    #[test]
+    fn mpmc_delivers_each_item_once() {
+        const PRODUCERS: usize = 4;
+        const CONSUMERS: usize = 4;
+        const ITEMS: usize = 20_000;
+
+        let queue: Arc<BoundedQueue<usize>> = Arc::new(BoundedQueue::new(8));
+        let seen: Arc<Vec<AtomicUsize>> = Arc::new(
+            (0..PRODUCERS * ITEMS)
+                .map(|_| AtomicUsize::new(0))
+                .collect(),
+        );
+        let received = Arc::new(AtomicUsize::new(0));
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|producer| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    for n in 0..ITEMS {
+                        let mut item = producer * ITEMS + n;
+                        while let Err((_, back)) = queue.push(item) {
+                            item = back;
+                            thread::yield_now();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let consumers: Vec<_> = (0..CONSUMERS)
+            .map(|_| {
+                let (queue, seen, received) = (queue.clone(), seen.clone(), received.clone());
+                thread::spawn(move || {
+                    while received.load(Ordering::Relaxed) < PRODUCERS * ITEMS {
+                        match queue.dequeue() {
+                            Some(item) => {
+                                seen[item].fetch_add(1, Ordering::Relaxed);
+                                received.fetch_add(1, Ordering::Relaxed);
+                            }
+                            None => thread::yield_now(),
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        producers
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+        consumers
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+        assert!(seen.iter().all(|count| count.load(Ordering::Relaxed) == 1));
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn timeouts() {
+        let queue = BoundedQueue::new(1);
+        let start = Instant::now();
+        assert_eq!(
+            queue.dequeue_timeout(Duration::from_millis(20)),
+            Err(BoundedQueueError::Timeout)
+        );
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        queue.enqueue(1).unwrap();
+        let start = Instant::now();
+        assert_eq!(
+            queue.enqueue_timeout(2, Duration::from_millis(20)),
+            Err(BoundedQueueError::Timeout)
+        );
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        // a deadline already passed still takes what's there
+        assert_eq!(queue.dequeue_deadline(start), Ok(1));
+        assert_eq!(
+            queue.dequeue_deadline(start),
+            Err(BoundedQueueError::Timeout)
+        );
+        assert_eq!(queue.enqueue_deadline(3, start), Ok(()));
+
+        // timeouts past what `Instant` can hold just mean no deadline
+        assert_eq!(queue.dequeue_timeout(Duration::MAX), Ok(3));
+        assert_eq!(queue.enqueue_timeout(4, Duration::MAX), Ok(()));
+    }
+
+    #[test]
+    fn timeout_wakes_on_item() {
+        let queue: Arc<BoundedQueue<u32>> = Arc::new(BoundedQueue::new(1));
+        let sender = queue.clone();
+
+        let handle = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(20));
+            sender.enqueue(7).unwrap();
+            sender.enqueue_timeout(8, Duration::from_secs(10))
+        });
+
+        assert_eq!(queue.dequeue_timeout(Duration::from_secs(10)), Ok(7));
+        assert_eq!(queue.dequeue_timeout(Duration::from_secs(10)), Ok(8));
+        assert_eq!(handle.join().unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn blocking_without_lost_wakeups() {
+        const ITEMS: usize = 10_000;
+
+        // a single slot makes every operation hand off to a sleeping thread
+        let queue: Arc<BoundedQueue<usize>> = Arc::new(BoundedQueue::new(1));
+        let threads: Vec<_> = (0..2)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || (0..ITEMS).for_each(|n| queue.enqueue_blocking(n).unwrap()))
+            })
+            .chain((0..2).map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    (0..ITEMS).for_each(|_| {
+                        queue.dequeue_blocking().unwrap();
+                    })
+                })
+            }))
+            .collect();
+
+        threads
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn close() {
+        let queue = BoundedQueue::new(2);
+        queue.enqueue(1).unwrap();
+        queue.enqueue(2).unwrap();
+        assert!(!queue.is_closed());
+        assert!(queue.close());
+        assert!(!queue.close());
+        assert!(queue.is_closed());
+
+        assert_eq!(queue.enqueue(3), Err(BoundedQueueError::Closed));
+        assert_eq!(queue.enqueue_blocking(3), Err(BoundedQueueError::Closed));
+
+        // what was queued before closing is still delivered
+        assert_eq!(queue.dequeue_blocking(), Ok(1));
+        assert_eq!(queue.dequeue(), Some(2));
+        assert_eq!(queue.dequeue_blocking(), Err(BoundedQueueError::Closed));
+        assert_eq!(
+            queue.dequeue_timeout(Duration::from_secs(10)),
+            Err(BoundedQueueError::Closed)
+        );
+    }
+
+    #[test]
+    fn close_wakes_waiters() {
+        let queue: Arc<BoundedQueue<u32>> = Arc::new(BoundedQueue::new(1));
+        queue.enqueue(0).unwrap();
+
+        let full = queue.clone();
+        let blocked_writer = thread::spawn(move || full.enqueue_blocking(1));
+        let empty: Arc<BoundedQueue<u32>> = Arc::new(BoundedQueue::new(1));
+        let waiting = empty.clone();
+        let blocked_reader = thread::spawn(move || waiting.dequeue_blocking());
+
+        thread::sleep(Duration::from_millis(20));
+        queue.close();
+        empty.close();
+        assert_eq!(
+            blocked_writer.join().unwrap(),
+            Err(BoundedQueueError::Closed)
+        );
+        assert_eq!(
+            blocked_reader.join().unwrap(),
+            Err(BoundedQueueError::Closed)
+        );
+        assert_eq!(queue.dequeue_blocking(), Ok(0));
+    }
+
+    #[test]
+    fn consumers_stop_after_producers_close() {
+        let queue: Arc<BoundedQueue<u64>> = Arc::new(BoundedQueue::new(4));
+        let consumers: Vec<_> = (0..3)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    let mut sum = 0;
+                    while let Ok(item) = queue.dequeue_blocking() {
+                        sum += item;
+                    }
+                    sum
+                })
+            })
+            .collect();
+
+        (1..=1000).for_each(|n| queue.enqueue_blocking(n).unwrap());
+        queue.close();
+        let total: u64 = consumers
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum();
+        assert_eq!(total, 500_500);
+    }
+
+    #[test]
+    fn batches() {
+        let queue = BoundedQueue::new(4);
+        assert_eq!(queue.enqueue_many(&[1, 2, 3, 4, 5, 6]), 4);
+        assert_eq!(queue.enqueue_many(&[7]), 0);
+
+        let mut out = crate::vec::Vec::new();
+        assert_eq!(queue.dequeue_into(&mut out, 3), 3);
+        assert_eq!(out, [1, 2, 3]);
+
+        // the run wraps around the end of the buffer
+        assert_eq!(queue.enqueue_many(&[5, 6, 7]), 3);
+        assert_eq!(queue.dequeue_into(&mut out, 10), 4);
+        assert_eq!(out, [1, 2, 3, 4, 5, 6, 7]);
+        assert_eq!(queue.dequeue_into(&mut out, 10), 0);
+
+        let mut rest = 10..20;
+        assert_eq!(queue.enqueue_iter(rest.by_ref()), (4, Some(14)));
+        assert_eq!(rest.next(), Some(15));
+        assert_eq!(queue.dequeue_into(&mut out, 2), 2);
+        assert_eq!(queue.len(), 2);
+
+        queue.close();
+        assert_eq!(queue.enqueue_many(&[1]), 0);
+        assert_eq!(queue.enqueue_iter([1]), (0, None));
+        assert_eq!(queue.dequeue_into(&mut out, 10), 2);
+        assert_eq!(out[7..], [10, 11, 12, 13]);
+    }
+
+    #[test]
+    fn panicking_clone_leaves_queue_usable() {
+        struct Fragile(u32);
+
+        impl Clone for Fragile {
+            fn clone(&self) -> Fragile {
+                assert!(self.0 != 3, "clone failed");
+                Fragile(self.0)
+            }
+        }
+
+        let queue = BoundedQueue::new(8);
+        let items: Vec<Fragile> = (0..6).map(Fragile).collect();
+        let result =
+            std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| queue.enqueue_many(&items)));
+        assert!(result.is_err());
+
+        // nothing was claimed and left unwritten, so readers aren't stuck
+        queue.enqueue_many(&items[4..]);
+        let mut out = crate::vec::Vec::new();
+        assert_eq!(queue.dequeue_into(&mut out, 10), 2);
+        assert!(out.iter().map(|item| item.0).eq([4, 5]));
+        assert_eq!(
+            queue
+                .dequeue_timeout(Duration::from_millis(10))
+                .map(|item| item.0),
+            Err(BoundedQueueError::Timeout)
+        );
+    }
+
+    #[test]
+    fn batches_of_owned_items() {
+        let shared = Arc::new(());
+        let queue = BoundedQueue::new(3);
+        assert_eq!(queue.enqueue_many(&[shared.clone(), shared.clone()]), 2);
+        assert_eq!(Arc::strong_count(&shared), 3);
+
+        let mut out = crate::vec::Vec::new();
+        assert_eq!(queue.dequeue_into(&mut out, 1), 1);
+        drop(out);
+        assert_eq!(Arc::strong_count(&shared), 2);
+        drop(queue);
+        assert_eq!(Arc::strong_count(&shared), 1);
+    }
+
+    #[test]
+    fn concurrent_batches_deliver_each_item_once() {
+        const PRODUCERS: usize = 3;
+        const ITEMS: usize = 20_000;
+        let queue: Arc<BoundedQueue<usize>> = Arc::new(BoundedQueue::new(64));
+        let seen: Arc<Vec<AtomicUsize>> = Arc::new(
+            (0..PRODUCERS * ITEMS)
+                .map(|_| AtomicUsize::new(0))
+                .collect(),
+        );
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|producer| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    let items: Vec<usize> = (producer * ITEMS..(producer + 1) * ITEMS).collect();
+                    let mut sent = 0;
+                    while sent < ITEMS {
+                        let end = (sent + 1 + sent % 37).min(ITEMS);
+                        match queue.enqueue_many(&items[sent..end]) {
+                            0 => thread::yield_now(),
+                            n => sent += n,
+                        }
+                    }
+                })
+            })
+            .collect();
+        let consumers: Vec<_> = (0..2)
+            .map(|_| {
+                let queue = queue.clone();
+                let seen = seen.clone();
+                thread::spawn(move || {
+                    let mut out = crate::vec::Vec::new();
+                    while !queue.is_closed() || !queue.is_empty() {
+                        if queue.dequeue_into(&mut out, 16) == 0 {
+                            thread::yield_now();
+                        }
+                        while let Some(item) = out.pop() {
+                            seen[item].fetch_add(1, Ordering::SeqCst);
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        producers
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+        queue.close();
+        consumers
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+        assert!(seen.iter().all(|count| count.load(Ordering::SeqCst) == 1));
+    }
+
+    #[test]
+    fn overflow_policies() {
+        let reject = BoundedQueue::new(2);
+        assert_eq!(reject.overflow_policy(), OverflowPolicy::Reject);
+        reject.enqueue(1).unwrap();
+        reject.enqueue(2).unwrap();
+        assert_eq!(reject.enqueue_overflowing(3), Err(BoundedQueueError::Full));
+        assert_eq!(reject.dropped_count(), 0);
+
+        let newest = BoundedQueue::with_overflow_policy(2, OverflowPolicy::DropNewest);
+        assert_eq!(newest.enqueue_overflowing(1), Ok(None));
+        newest.enqueue(2).unwrap();
+        assert_eq!(newest.enqueue_overflowing(3), Ok(Some(3)));
+        assert_eq!(newest.enqueue(4), Ok(()));
+        assert_eq!(newest.dropped_count(), 2);
+        // batch writes don't apply the policy
+        assert_eq!(newest.enqueue_many(&[5]), 0);
+        assert_eq!(newest.enqueue_iter([6]), (0, Some(6)));
+        assert_eq!(newest.dropped_count(), 2);
+        assert_eq!(newest.dequeue(), Some(1));
+        assert_eq!(newest.dequeue(), Some(2));
+
+        let oldest = BoundedQueue::with_overflow_policy(2, OverflowPolicy::OverwriteOldest);
+        oldest.enqueue(1).unwrap();
+        oldest.enqueue(2).unwrap();
+        assert_eq!(oldest.enqueue_overflowing(3), Ok(Some(1)));
+        assert_eq!(oldest.enqueue(4), Ok(()));
+        assert_eq!(oldest.dropped_count(), 2);
+        assert_eq!(oldest.len(), 2);
+        assert_eq!(oldest.dequeue(), Some(3));
+        assert_eq!(oldest.dequeue(), Some(4));
+
+        oldest.close();
+        assert_eq!(
+            oldest.enqueue_overflowing(5),
+            Err(BoundedQueueError::Closed)
+        );
+        assert_eq!(oldest.dropped_count(), 2);
+    }
+
+    #[test]
+    fn overwrite_oldest_under_contention() {
+        const PRODUCERS: usize = 4;
+        const ITEMS: usize = 10_000;
+        let queue: Arc<BoundedQueue<usize>> = Arc::new(BoundedQueue::with_overflow_policy(
+            8,
+            OverflowPolicy::OverwriteOldest,
+        ));
+
+        let producers: Vec<_> = (0..PRODUCERS)
+            .map(|_| {
+                let queue = queue.clone();
+                thread::spawn(move || {
+                    let mut handed_back = 0;
+                    for n in 0..ITEMS {
+                        if queue.enqueue_overflowing(n).unwrap().is_some() {
+                            handed_back += 1;
+                        }
+                    }
+                    handed_back
+                })
+            })
+            .collect();
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                let mut received = 0;
+                while !queue.is_closed() || !queue.is_empty() {
+                    match queue.dequeue() {
+                        Some(_) => received += 1,
+                        None => thread::yield_now(),
+                    }
+                }
+                received
+            })
+        };
+
+        let handed_back: usize = producers
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .sum();
+        queue.close();
+        let received = consumer.join().unwrap();
+
+        // every item was either delivered or counted as dropped
+        assert!(handed_back <= queue.dropped_count());
+        assert_eq!(received + queue.dropped_count(), PRODUCERS * ITEMS);
     }
 }
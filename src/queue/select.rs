@@ -0,0 +1,374 @@
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Wake, Waker},
+    thread::{self, Thread},
+    time::{Duration, Instant},
+};
+
+use super::{event::Event, BoundedQueue, BoundedQueueError};
+use crate::{slot_map::Key, vec::Vec};
+
+// where the next select starts looking, so no queue is always checked first
+static NEXT_START: AtomicUsize = AtomicUsize::new(0);
+
+trait Operation<R> {
+    // runs the operation if it can complete now; consumes it when it does
+    fn attempt(&mut self) -> Option<R>;
+    // signalled whenever a retry could succeed
+    fn event(&self) -> &Event;
+}
+
+struct RecvOp<'a, T, F> {
+    queue: &'a BoundedQueue<T>,
+    f: Option<F>,
+}
+
+impl<T, R, F: FnOnce(Result<T, BoundedQueueError>) -> R> Operation<R> for RecvOp<'_, T, F> {
+    fn attempt(&mut self) -> Option<R> {
+        // checked first: closed and then found empty means drained for good
+        let closed = self.queue.is_closed();
+        let result = match self.queue.dequeue() {
+            Some(item) => Ok(item),
+            None if closed => Err(BoundedQueueError::Closed),
+            None => return None,
+        };
+        Some((self.f.take().unwrap())(result))
+    }
+
+    fn event(&self) -> &Event {
+        &self.queue.write_event
+    }
+}
+
+struct SendOp<'a, T, F> {
+    queue: &'a BoundedQueue<T>,
+    item: Option<T>,
+    f: Option<F>,
+}
+
+impl<T, R, F: FnOnce(Result<(), BoundedQueueError>) -> R> Operation<R> for SendOp<'_, T, F> {
+    fn attempt(&mut self) -> Option<R> {
+        let result = match self.queue.push(self.item.take().unwrap()) {
+            Ok(()) => Ok(()),
+            Err((BoundedQueueError::Full, item)) => {
+                self.item = Some(item);
+                return None;
+            }
+            Err((error, _)) => Err(error),
+        };
+        Some((self.f.take().unwrap())(result))
+    }
+
+    fn event(&self) -> &Event {
+        &self.queue.read_event
+    }
+}
+
+struct Unparker(Thread);
+
+impl Wake for Unparker {
+    fn wake(self: Arc<Self>) {
+        self.0.unpark();
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+// Waits for the first of several queue operations that can complete and
+// performs only that one. Each operation maps its outcome to a common `R`,
+// so queues of different item types can be mixed:
+//
+//     let message = Select::new()
+//         .recv(&commands, Message::Command)
+//         .recv(&events, Message::Event)
+//         .wait();
+//
+// While blocked, the thread registers one waker with every queue involved
+// and parks until any of them signals.
+pub struct Select<'a, R> {
+    operations: Vec<Box<dyn Operation<R> + 'a>>,
+}
+
+impl<'a, R> Select<'a, R> {
+    pub fn new() -> Select<'a, R> {
+        Select {
+            operations: Vec::new(),
+        }
+    }
+
+    // `f` gets the item, or `Closed` once the queue is closed and drained
+    pub fn recv<T, F>(mut self, queue: &'a BoundedQueue<T>, f: F) -> Select<'a, R>
+    where
+        T: 'a,
+        F: FnOnce(Result<T, BoundedQueueError>) -> R + 'a,
+    {
+        self.operations.push(Box::new(RecvOp { queue, f: Some(f) }));
+        self
+    }
+
+    // `f` learns whether `item` went in; it is dropped if the queue is
+    // closed, and also if some other operation is selected instead
+    pub fn send<T, F>(mut self, queue: &'a BoundedQueue<T>, item: T, f: F) -> Select<'a, R>
+    where
+        T: 'a,
+        F: FnOnce(Result<(), BoundedQueueError>) -> R + 'a,
+    {
+        self.operations.push(Box::new(SendOp {
+            queue,
+            item: Some(item),
+            f: Some(f),
+        }));
+        self
+    }
+
+    pub fn try_select(mut self) -> Option<R> {
+        let start = self.start();
+        self.attempt(start)
+    }
+
+    pub fn wait(mut self) -> R {
+        self.wait_until(None).unwrap()
+    }
+
+    // `None` if nothing could complete in time; a timeout too far out to be
+    // represented waits without one
+    pub fn wait_timeout(mut self, timeout: Duration) -> Option<R> {
+        self.wait_until(Instant::now().checked_add(timeout))
+    }
+
+    pub fn wait_deadline(mut self, deadline: Instant) -> Option<R> {
+        self.wait_until(Some(deadline))
+    }
+
+    fn start(&self) -> usize {
+        assert!(
+            !self.operations.is_empty(),
+            "select needs at least one operation"
+        );
+        NEXT_START.fetch_add(1, Ordering::Relaxed) % self.operations.len()
+    }
+
+    fn attempt(&mut self, start: usize) -> Option<R> {
+        let len = self.operations.len();
+        (0..len).find_map(|offset| self.operations[(start + offset) % len].attempt())
+    }
+
+    // Same protocol as a single future: register everywhere, retry, and only
+    // then park. A notification that lands in between unparks the thread
+    // ahead of time, so the park returns at once.
+    fn wait_until(&mut self, deadline: Option<Instant>) -> Option<R> {
+        let start = self.start();
+        if let Some(result) = self.attempt(start) {
+            return Some(result);
+        }
+
+        let waker = Waker::from(Arc::new(Unparker(thread::current())));
+        let mut keys: Vec<Option<Key>> = crate::vector![None; self.operations.len()];
+        let result = loop {
+            for (operation, key) in self.operations.iter().zip(keys.iter_mut()) {
+                operation.event().register(key, &waker);
+            }
+            if let Some(result) = self.attempt(start) {
+                break Some(result);
+            }
+
+            match deadline {
+                None => thread::park(),
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        break None;
+                    }
+                    thread::park_timeout(deadline - now);
+                }
+            }
+        };
+
+        for (operation, key) in self.operations.iter().zip(keys.iter_mut()) {
+            operation.event().unregister(key);
+        }
+        result
+    }
+}
+
+impl<R> Default for Select<'_, R> {
+    fn default() -> Self {
+        Select::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::Arc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::Select;
+    use crate::{
+        queue::{BoundedQueue, BoundedQueueError},
+        vec::Vec,
+    };
+
+    #[derive(Debug, PartialEq)]
+    enum Message {
+        Number(u32),
+        Text(String),
+        Closed,
+    }
+
+    fn receive(numbers: &BoundedQueue<u32>, texts: &BoundedQueue<String>) -> Message {
+        Select::new()
+            .recv(numbers, |item| {
+                item.map_or(Message::Closed, Message::Number)
+            })
+            .recv(texts, |item| item.map_or(Message::Closed, Message::Text))
+            .wait()
+    }
+
+    #[test]
+    fn picks_the_ready_queue() {
+        let numbers = BoundedQueue::new(2);
+        let texts = BoundedQueue::new(2);
+        texts.enqueue(String::from("hi")).unwrap();
+        assert_eq!(receive(&numbers, &texts), Message::Text(String::from("hi")));
+
+        numbers.enqueue(7).unwrap();
+        assert_eq!(receive(&numbers, &texts), Message::Number(7));
+
+        texts.close();
+        assert_eq!(receive(&numbers, &texts), Message::Closed);
+    }
+
+    #[test]
+    fn only_the_selected_operation_runs() {
+        let full = BoundedQueue::new(1);
+        let open = BoundedQueue::new(1);
+        full.enqueue(1).unwrap();
+
+        let sent = Select::new()
+            .send(&full, 2, |result| result.map(|()| "full"))
+            .send(&open, 3, |result| result.map(|()| "open"))
+            .wait();
+        assert_eq!(sent, Ok("open"));
+        assert_eq!(full.dequeue(), Some(1));
+        assert_eq!(full.dequeue(), None);
+        assert_eq!(open.dequeue(), Some(3));
+
+        let items = BoundedQueue::new(1);
+        items.enqueue(4).unwrap();
+        let received = Select::new()
+            .send(&full, 5, |_| None)
+            .recv(&items, |item| item.ok())
+            .try_select();
+        // both are ready, exactly one of them happened
+        match received {
+            Some(None) => assert_eq!((full.len(), items.len()), (1, 1)),
+            Some(Some(4)) => assert_eq!((full.len(), items.len()), (0, 0)),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn timeout() {
+        let empty: BoundedQueue<u32> = BoundedQueue::new(1);
+        assert_eq!(Select::new().recv(&empty, |_| ()).try_select(), None);
+
+        let start = Instant::now();
+        let result = Select::new()
+            .recv(&empty, |item| item)
+            .wait_timeout(Duration::from_millis(20));
+        assert_eq!(result, None);
+        assert!(start.elapsed() >= Duration::from_millis(20));
+
+        empty.close();
+        assert_eq!(
+            Select::new()
+                .recv(&empty, |item| item)
+                .wait_timeout(Duration::from_secs(10)),
+            Some(Err(BoundedQueueError::Closed))
+        );
+        assert_eq!(
+            Select::new()
+                .recv(&empty, |item| item)
+                .wait_timeout(Duration::MAX),
+            Some(Err(BoundedQueueError::Closed))
+        );
+    }
+
+    #[test]
+    fn wakes_on_any_queue() {
+        let numbers: Arc<BoundedQueue<u32>> = Arc::new(BoundedQueue::new(1));
+        let texts: Arc<BoundedQueue<String>> = Arc::new(BoundedQueue::new(1));
+
+        let sender = {
+            let texts = texts.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(20));
+                texts.enqueue_blocking(String::from("late")).unwrap();
+            })
+        };
+        assert_eq!(
+            receive(&numbers, &texts),
+            Message::Text(String::from("late"))
+        );
+        sender.join().unwrap();
+
+        const ITEMS: u32 = 2000;
+        let producers: Vec<_> = (0..2)
+            .map(|_| {
+                let numbers = numbers.clone();
+                let texts = texts.clone();
+                thread::spawn(move || {
+                    for n in 0..ITEMS {
+                        numbers.enqueue_blocking(n).unwrap();
+                        texts.enqueue_blocking(n.to_string()).unwrap();
+                    }
+                })
+            })
+            .collect();
+
+        let (mut sum, mut count) = (0, 0);
+        for _ in 0..4 * ITEMS {
+            match receive(&numbers, &texts) {
+                Message::Number(n) => sum += n,
+                Message::Text(text) => count += text.len(),
+                Message::Closed => unreachable!(),
+            }
+        }
+        producers
+            .into_iter()
+            .for_each(|handle| handle.join().unwrap());
+        assert_eq!(sum, ITEMS * (ITEMS - 1));
+        assert_eq!(
+            count,
+            2 * (0..ITEMS).map(|n| n.to_string().len()).sum::<usize>()
+        );
+    }
+
+    #[test]
+    fn fair_across_ready_queues() {
+        let first = BoundedQueue::new(100);
+        let second = BoundedQueue::new(100);
+        for _ in 0..100 {
+            first.enqueue(1).unwrap();
+            second.enqueue(2).unwrap();
+        }
+
+        let mut picked = [0, 0];
+        for _ in 0..100 {
+            let which = Select::new()
+                .recv(&first, |_| 0)
+                .recv(&second, |_| 1)
+                .wait();
+            picked[which] += 1;
+        }
+        assert!(picked[0] > 20 && picked[1] > 20, "{:?}", picked);
+    }
+}
@@ -0,0 +1,417 @@
+use std::{
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    ptr, slice,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use super::cache_padded::CachePadded;
+
+// Positions run over `0..2 * cap` rather than `0..cap`, so equal positions
+// mean empty and positions `cap` apart mean full, without giving up a slot.
+struct Ring<T> {
+    // next position to read, only advanced by the consumer
+    head: CachePadded<AtomicUsize>,
+    // next position to write, only advanced by the producer
+    tail: CachePadded<AtomicUsize>,
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+unsafe impl<T: Send> Send for Ring<T> {}
+unsafe impl<T: Send> Sync for Ring<T> {}
+
+impl<T> Ring<T> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn len(&self, head: usize, tail: usize) -> usize {
+        (tail + 2 * self.capacity() - head) % (2 * self.capacity())
+    }
+
+    fn advance(&self, pos: usize, n: usize) -> usize {
+        (pos + n) % (2 * self.capacity())
+    }
+
+    fn index(&self, pos: usize) -> usize {
+        pos % self.capacity()
+    }
+
+    // Derived from the whole buffer rather than one cell, so the pointer may
+    // also be used for a run of slots (`UnsafeCell` is `repr(transparent)`).
+    fn slot(&self, pos: usize) -> *mut MaybeUninit<T> {
+        unsafe { UnsafeCell::raw_get(self.slots.as_ptr().add(self.index(pos))) }
+    }
+}
+
+impl<T> Drop for Ring<T> {
+    fn drop(&mut self) {
+        let mut head = *self.head.get_mut();
+        let tail = *self.tail.get_mut();
+        while head != tail {
+            unsafe { (*self.slot(head)).assume_init_drop() };
+            head = self.advance(head, 1);
+        }
+    }
+}
+
+// A wait-free ring buffer for exactly one producer and one consumer thread.
+// Each side owns one index and only reads the other's, so a push or pop is a
+// slot access plus one release store; the other side's index is cached and
+// only reloaded when the ring looks full or empty.
+pub fn ring<T>(capacity: usize) -> (Producer<T>, Consumer<T>) {
+    assert!(capacity > 0, "capacity must be positive");
+
+    let ring = Arc::new(Ring {
+        head: CachePadded::new(AtomicUsize::new(0)),
+        tail: CachePadded::new(AtomicUsize::new(0)),
+        slots: (0..capacity)
+            .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+            .collect(),
+    });
+    let producer = Producer {
+        ring: ring.clone(),
+        head: 0,
+        tail: 0,
+        writable: 0,
+    };
+    let consumer = Consumer {
+        ring,
+        head: 0,
+        tail: 0,
+    };
+    (producer, consumer)
+}
+
+pub struct Producer<T> {
+    ring: Arc<Ring<T>>,
+    // last seen consumer position
+    head: usize,
+    // own position, mirrored in `ring.tail`
+    tail: usize,
+    // length of the last `write_slice`, the most `commit` may publish
+    writable: usize,
+}
+
+impl<T> Producer<T> {
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    // how many items could be pushed right now
+    pub fn slots(&mut self) -> usize {
+        self.refresh_head();
+        self.capacity() - self.ring.len(self.head, self.tail)
+    }
+
+    pub fn is_full(&mut self) -> bool {
+        self.slots() == 0
+    }
+
+    // hands the item back if the ring is full
+    pub fn push(&mut self, item: T) -> Result<(), T> {
+        if self.ring.len(self.head, self.tail) == self.capacity() && self.is_full() {
+            return Err(item);
+        }
+
+        unsafe { (*self.ring.slot(self.tail)).write(item) };
+        self.commit_unchecked(1);
+        Ok(())
+    }
+
+    // The free slots up to the end of the buffer, to be filled in place (e.g.
+    // by a `read` call) and then published with `commit`. When the free space
+    // wraps around, the rest becomes available after committing this part.
+    pub fn write_slice(&mut self) -> &mut [MaybeUninit<T>] {
+        let free = self.slots();
+        let start = self.ring.index(self.tail);
+        let len = free.min(self.capacity() - start);
+        self.writable = len;
+        unsafe { slice::from_raw_parts_mut(self.ring.slot(self.tail), len) }
+    }
+
+    /// # Safety
+    ///
+    /// The first `n` slots of the last `write_slice` must have been initialized.
+    pub unsafe fn commit(&mut self, n: usize) {
+        assert!(n <= self.writable, "committed too many");
+        self.commit_unchecked(n);
+    }
+
+    // any earlier `write_slice` no longer starts at the tail
+    fn commit_unchecked(&mut self, n: usize) {
+        self.writable = 0;
+        self.tail = self.ring.advance(self.tail, n);
+        self.ring.tail.store(self.tail, Ordering::Release);
+    }
+
+    fn refresh_head(&mut self) {
+        self.head = self.ring.head.load(Ordering::Acquire);
+    }
+}
+
+impl<T: Copy> Producer<T> {
+    // copies as many items as fit, returning how many that was
+    pub fn push_slice(&mut self, mut items: &[T]) -> usize {
+        let mut pushed = 0;
+        while !items.is_empty() {
+            let slots = self.write_slice();
+            let n = slots.len().min(items.len());
+            if n == 0 {
+                break;
+            }
+            unsafe {
+                ptr::copy_nonoverlapping(items.as_ptr(), slots.as_mut_ptr() as *mut T, n);
+            }
+            self.commit_unchecked(n);
+            items = &items[n..];
+            pushed += n;
+        }
+        pushed
+    }
+}
+
+pub struct Consumer<T> {
+    ring: Arc<Ring<T>>,
+    // own position, mirrored in `ring.head`
+    head: usize,
+    // last seen producer position
+    tail: usize,
+}
+
+impl<T> Consumer<T> {
+    pub fn capacity(&self) -> usize {
+        self.ring.capacity()
+    }
+
+    // how many items could be popped right now
+    pub fn len(&mut self) -> usize {
+        self.refresh_tail();
+        self.ring.len(self.head, self.tail)
+    }
+
+    pub fn is_empty(&mut self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if self.head == self.tail && self.is_empty() {
+            return None;
+        }
+
+        let item = unsafe { (*self.ring.slot(self.head)).assume_init_read() };
+        self.release(1);
+        Some(item)
+    }
+
+    pub fn peek(&mut self) -> Option<&T> {
+        if self.head == self.tail && self.is_empty() {
+            return None;
+        }
+        unsafe { Some((*self.ring.slot(self.head)).assume_init_ref()) }
+    }
+
+    // The readable items up to the end of the buffer, to be processed in
+    // place and then freed with `consume`.
+    pub fn read_slice(&mut self) -> &[T] {
+        let available = self.len();
+        let start = self.ring.index(self.head);
+        let len = available.min(self.capacity() - start);
+        unsafe { slice::from_raw_parts(self.ring.slot(self.head) as *const T, len) }
+    }
+
+    // drops the first `n` items of `read_slice` and frees their slots
+    pub fn consume(&mut self, n: usize) {
+        let start = self.ring.index(self.head);
+        let readable = self.ring.len(self.head, self.tail);
+        assert!(
+            n <= readable.min(self.capacity() - start),
+            "consumed too many"
+        );
+
+        unsafe {
+            let items = slice::from_raw_parts_mut(self.ring.slot(self.head) as *mut T, n);
+            ptr::drop_in_place(items);
+        }
+        self.release(n);
+    }
+
+    fn release(&mut self, n: usize) {
+        self.head = self.ring.advance(self.head, n);
+        self.ring.head.store(self.head, Ordering::Release);
+    }
+
+    fn refresh_tail(&mut self) {
+        self.tail = self.ring.tail.load(Ordering::Acquire);
+    }
+}
+
+impl<T: Copy> Consumer<T> {
+    // copies as many items as are available into `out`, returning how many
+    pub fn pop_slice(&mut self, out: &mut [T]) -> usize {
+        let mut popped = 0;
+        while popped < out.len() {
+            let items = self.read_slice();
+            let n = items.len().min(out.len() - popped);
+            if n == 0 {
+                break;
+            }
+            out[popped..popped + n].copy_from_slice(&items[..n]);
+            self.release(n);
+            popped += n;
+        }
+        popped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        io::{Cursor, Read},
+        mem::MaybeUninit,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        thread,
+    };
+
+    use super::ring;
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn push_pop() {
+        let (mut tx, mut rx) = ring(3);
+        assert_eq!(rx.pop(), None);
+        tx.push(1).unwrap();
+        tx.push(2).unwrap();
+        tx.push(3).unwrap();
+        assert!(tx.is_full());
+        assert_eq!(tx.push(4), Err(4));
+
+        assert_eq!(rx.len(), 3);
+        assert_eq!(rx.peek(), Some(&1));
+        assert_eq!(rx.pop(), Some(1));
+        tx.push(4).unwrap();
+        assert_eq!(rx.pop(), Some(2));
+        assert_eq!(rx.pop(), Some(3));
+        assert_eq!(rx.pop(), Some(4));
+        assert!(rx.is_empty());
+        assert_eq!(tx.slots(), 3);
+    }
+
+    #[test]
+    fn batches_wrap_around() {
+        let (mut tx, mut rx) = ring(5);
+        assert_eq!(tx.push_slice(&[0, 1, 2, 3]), 4);
+        let mut out = [0; 3];
+        assert_eq!(rx.pop_slice(&mut out), 3);
+        assert_eq!(out, [0, 1, 2]);
+
+        // the free space wraps, both parts get filled
+        assert_eq!(tx.push_slice(&[4, 5, 6, 7, 8, 9]), 4);
+        assert_eq!(rx.read_slice(), [3, 4]);
+        rx.consume(2);
+        assert_eq!(rx.read_slice(), [5, 6, 7]);
+
+        let mut out = [0; 10];
+        assert_eq!(tx.push_slice(&[8, 9]), 2);
+        assert_eq!(rx.pop_slice(&mut out), 5);
+        assert_eq!(out[..5], [5, 6, 7, 8, 9]);
+        assert_eq!(rx.pop_slice(&mut out), 0);
+    }
+
+    #[test]
+    fn zero_copy_io() {
+        let mut source = Cursor::new((0..=255u8).collect::<Vec<u8>>());
+        let mut sink = Vec::new();
+        let (mut tx, mut rx) = ring::<u8>(64);
+
+        loop {
+            let slots = tx.write_slice();
+            // `read` gets a `&mut [u8]`, so the slots have to be initialized first
+            slots.fill(MaybeUninit::new(0));
+            let buf = unsafe { &mut *(slots as *mut [MaybeUninit<u8>] as *mut [u8]) };
+            let read = source.read(buf).unwrap();
+            unsafe { tx.commit(read) };
+
+            let items = rx.read_slice();
+            let n = items.len();
+            sink.extend_from_slice(items);
+            rx.consume(n);
+            if read == 0 && n == 0 {
+                break;
+            }
+        }
+        assert!(sink.iter().copied().eq(0..=255));
+    }
+
+    #[test]
+    #[should_panic(expected = "committed too many")]
+    fn commit_is_limited_to_the_write_slice() {
+        let (mut tx, mut rx) = ring::<u8>(4);
+        tx.push_slice(&[1, 2]);
+        assert_eq!(rx.pop_slice(&mut [0; 2]), 2);
+
+        // the free space wraps around, the slice only reaches the end
+        tx.write_slice().fill(MaybeUninit::new(3));
+        unsafe { tx.commit(3) };
+    }
+
+    #[test]
+    fn drops_items() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let (mut tx, mut rx) = ring(4);
+        for _ in 0..4 {
+            assert!(tx.push(DropCounter(drops.clone())).is_ok());
+        }
+        drop(tx.push(DropCounter(drops.clone())));
+        assert_eq!(drops.load(Ordering::Relaxed), 1);
+
+        drop(rx.pop());
+        rx.consume(1);
+        assert_eq!(drops.load(Ordering::Relaxed), 3);
+
+        drop(tx);
+        drop(rx);
+        assert_eq!(drops.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn across_threads() {
+        let (mut tx, mut rx) = ring(16);
+
+        let producer = thread::spawn(move || {
+            for n in 0..100_000u64 {
+                let mut item = n.to_string();
+                while let Err(back) = tx.push(item) {
+                    item = back;
+                    thread::yield_now();
+                }
+            }
+        });
+
+        let mut expected = 0u64;
+        while expected < 100_000 {
+            match rx.pop() {
+                Some(item) => {
+                    assert_eq!(item, expected.to_string());
+                    expected += 1;
+                }
+                None => thread::yield_now(),
+            }
+        }
+        producer.join().unwrap();
+        assert_eq!(rx.pop(), None);
+    }
+}
@@ -0,0 +1,374 @@
+#![allow(dead_code)]
+
+use std::{
+    cell::{Cell, UnsafeCell},
+    fmt,
+    iter::FromIterator,
+    ops::{Index, IndexMut},
+    ptr,
+};
+
+use crate::vec::RawVec;
+
+// segment `k` holds `FIRST_SEGMENT << k` items
+const FIRST_SEGMENT_SHIFT: u32 = 3;
+const FIRST_SEGMENT: usize = 1 << FIRST_SEGMENT_SHIFT;
+const SEGMENTS: usize = (usize::BITS - FIRST_SEGMENT_SHIFT) as usize;
+
+// Items live in separately allocated segments that double in size and are
+// never reallocated, so an item stays at the same address until it is popped
+// or the vector is dropped. That is why `push` only needs `&self`: references
+// handed out earlier remain valid while more items are pushed.
+pub struct SegmentedVec<T> {
+    segments: UnsafeCell<[RawVec<T>; SEGMENTS]>,
+    allocated: Cell<usize>,
+    len: Cell<usize>,
+}
+
+impl<T> SegmentedVec<T> {
+    pub fn new() -> SegmentedVec<T> {
+        SegmentedVec {
+            segments: UnsafeCell::new(std::array::from_fn(|_| RawVec::new())),
+            allocated: Cell::new(0),
+            len: Cell::new(0),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len.get()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        segment_start(self.allocated.get())
+    }
+
+    pub fn push(&self, value: T) -> &T {
+        let index = self.len();
+        let (segment, offset) = locate(index);
+        if segment == self.allocated.get() {
+            // only the slot of the new segment is written, items already
+            // handed out live in other allocations and are not touched
+            unsafe {
+                (*self.segments.get())[segment] = RawVec::with_capacity(segment_len(segment))
+            };
+            self.allocated.set(segment + 1);
+        }
+
+        unsafe {
+            let slot = self.segment_ptr(segment).add(offset);
+            ptr::write(slot, value);
+            self.len.set(index + 1);
+            &*slot
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+
+        let (segment, offset) = locate(len - 1);
+        self.len.set(len - 1);
+        unsafe { Some(ptr::read(self.segment_ptr(segment).add(offset))) }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len() {
+            let (segment, offset) = locate(index);
+            unsafe { Some(&*self.segment_ptr(segment).add(offset)) }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len() {
+            let (segment, offset) = locate(index);
+            unsafe { Some(&mut *self.segment_ptr(segment).add(offset)) }
+        } else {
+            None
+        }
+    }
+
+    // frees segments no item lives in anymore
+    pub fn shrink_to_fit(&mut self) {
+        let needed = match self.len() {
+            0 => 0,
+            len => locate(len - 1).0 + 1,
+        };
+        let segments = &mut self.segments.get_mut()[needed..self.allocated.get()];
+        segments.iter_mut().for_each(|segment| *segment = RawVec::new());
+        self.allocated.set(needed);
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            vec: self,
+            index: 0,
+            end: self.len(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let end = self.len();
+        IterMut {
+            vec: self,
+            index: 0,
+            end,
+        }
+    }
+
+    fn segment_ptr(&self, segment: usize) -> *mut T {
+        unsafe { (*self.segments.get())[segment].ptr() }
+    }
+}
+
+fn segment_len(segment: usize) -> usize {
+    FIRST_SEGMENT << segment
+}
+
+fn segment_start(segment: usize) -> usize {
+    FIRST_SEGMENT * ((1 << segment) - 1)
+}
+
+fn locate(index: usize) -> (usize, usize) {
+    // shifting by the first segment's size makes every segment start at a power of two
+    let shifted = index + FIRST_SEGMENT;
+    let segment = (usize::BITS - 1 - shifted.leading_zeros() - FIRST_SEGMENT_SHIFT) as usize;
+    (segment, shifted - segment_len(segment))
+}
+
+impl<T> Drop for SegmentedVec<T> {
+    fn drop(&mut self) {
+        while self.pop().is_some() {}
+    }
+}
+
+impl<T> Default for SegmentedVec<T> {
+    fn default() -> SegmentedVec<T> {
+        SegmentedVec::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SegmentedVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Index<usize> for SegmentedVec<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for SegmentedVec<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<T> FromIterator<T> for SegmentedVec<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> SegmentedVec<T> {
+        let mut vec = SegmentedVec::new();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T> Extend<T> for SegmentedVec<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+// Walks by index rather than holding on to segments, so pushes made through
+// the same `&SegmentedVec` while iterating are fine; they just aren't visited.
+pub struct Iter<'a, T> {
+    vec: &'a SegmentedVec<T>,
+    index: usize,
+    end: usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.index == self.end {
+            None
+        } else {
+            self.index += 1;
+            self.vec.get(self.index - 1)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    vec: &'a mut SegmentedVec<T>,
+    index: usize,
+    end: usize,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.index == self.end {
+            return None;
+        }
+
+        let (segment, offset) = locate(self.index);
+        self.index += 1;
+        // every index is yielded once, so the references never alias
+        unsafe { Some(&mut *self.vec.segment_ptr(segment).add(offset)) }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.index;
+        (len, Some(len))
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a SegmentedVec<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut SegmentedVec<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::{locate, SegmentedVec};
+
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn segment_layout() {
+        assert_eq!(locate(0), (0, 0));
+        assert_eq!(locate(7), (0, 7));
+        assert_eq!(locate(8), (1, 0));
+        assert_eq!(locate(23), (1, 15));
+        assert_eq!(locate(24), (2, 0));
+        assert_eq!(locate(usize::MAX - 8).0, super::SEGMENTS - 1);
+
+        let vec: SegmentedVec<u8> = (0..25).collect();
+        assert_eq!(vec.capacity(), 8 + 16 + 32);
+    }
+
+    #[test]
+    fn push_pop_get() {
+        let mut vec = SegmentedVec::new();
+        (0..1000).for_each(|n| {
+            vec.push(n);
+        });
+        assert_eq!(vec.len(), 1000);
+        assert!((0..1000).all(|n| vec[n] == n));
+        assert_eq!(vec.get(1000), None);
+
+        vec[500] = 0;
+        assert_eq!(vec.get(500), Some(&0));
+        assert_eq!(vec.pop(), Some(999));
+        assert_eq!(vec.len(), 999);
+    }
+
+    #[test]
+    fn references_survive_pushes() {
+        let vec = SegmentedVec::new();
+        let first = vec.push(String::from("first"));
+        let addresses: Vec<*const String> = (0..10)
+            .map(|n| vec.push(n.to_string()) as *const String)
+            .collect();
+
+        for n in 0..10_000 {
+            vec.push(n.to_string());
+        }
+
+        assert_eq!(first, "first");
+        for (n, &address) in addresses.iter().enumerate() {
+            assert!(std::ptr::eq(address, &vec[n + 1]));
+        }
+    }
+
+    #[test]
+    fn iter() {
+        let mut vec: SegmentedVec<u32> = (0..100).collect();
+        for item in &mut vec {
+            *item += 1;
+        }
+        assert!(vec.iter().copied().eq(1..101));
+        assert_eq!(vec.iter().len(), 100);
+
+        let mut seen = 0;
+        for item in &vec {
+            if seen < 3 {
+                vec.push(*item);
+            }
+            seen += 1;
+        }
+        assert_eq!(seen, 100);
+        assert_eq!(vec.len(), 103);
+    }
+
+    #[test]
+    fn shrink_to_fit() {
+        let mut vec: SegmentedVec<u32> = (0..100).collect();
+        (0..90).for_each(|_| {
+            vec.pop();
+        });
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 24);
+        assert!(vec.iter().copied().eq(0..10));
+        vec.extend(10..20);
+        assert!(vec.iter().copied().eq(0..20));
+    }
+
+    #[test]
+    fn drop_items() {
+        let drops = Rc::new(Cell::new(0));
+        let mut vec = SegmentedVec::new();
+        (0..50).for_each(|_| {
+            vec.push(DropCounter(drops.clone()));
+        });
+        drop(vec.pop());
+        assert_eq!(drops.get(), 1);
+        drop(vec);
+        assert_eq!(drops.get(), 50);
+    }
+}
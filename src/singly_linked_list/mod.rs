@@ -41,13 +41,13 @@ impl<T> List<T> {
         self.head.as_mut().map(|node| &mut node.item)
     }
 
-    pub fn iter(&self) -> Iter<T> {
+    pub fn iter(&self) -> Iter<'_, T> {
         Iter {
             next: self.head.as_deref(),
         }
     }
 
-    pub fn iter_mut(&mut self) -> IterMut<T> {
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
         IterMut {
             next: self.head.as_deref_mut(),
         }
@@ -153,7 +153,9 @@ mod tests {
         list.push(2);
         assert_eq!(list.peek(), Some(&2));
 
-        list.peek_mut().map(|item| *item = 3);
+        if let Some(item) = list.peek_mut() {
+            *item = 3;
+        }
         assert_eq!(list.peek(), Some(&3));
     }
 
@@ -162,7 +164,6 @@ mod tests {
         let mut list = List::new();
         (0..10).for_each(|n| list.push(n));
         (0..10)
-            .into_iter()
             .rev()
             .zip(list.iter())
             .for_each(|(x, y)| assert_eq!(x, *y));
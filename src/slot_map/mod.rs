@@ -0,0 +1,360 @@
+#![allow(dead_code)]
+
+use std::{
+    mem,
+    ops::{Index, IndexMut},
+};
+
+use crate::vec::Vec;
+
+// A key stays valid until its entry is removed. The slot it points at may be
+// reused afterwards, but with a new generation, so the old key can't reach
+// the new entry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Key {
+    index: u32,
+    generation: u32,
+}
+
+impl Key {
+    pub fn index(&self) -> usize {
+        self.index as usize
+    }
+
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+enum Entry<T> {
+    Occupied(T),
+    Vacant { next_free: Option<u32> },
+}
+
+struct Slot<T> {
+    generation: u32,
+    entry: Entry<T>,
+}
+
+pub struct SlotMap<T> {
+    slots: Vec<Slot<T>>,
+    free_head: Option<u32>,
+    len: usize,
+}
+
+impl<T> SlotMap<T> {
+    pub fn new() -> SlotMap<T> {
+        SlotMap {
+            slots: Vec::new(),
+            free_head: None,
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(cap: usize) -> SlotMap<T> {
+        let mut map = SlotMap::new();
+        map.slots.reserve(cap);
+        map
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn insert(&mut self, value: T) -> Key {
+        self.insert_with_key(|_| value)
+    }
+
+    // lets the value store its own key, e.g. for nodes that link back to themselves
+    pub fn insert_with_key<F: FnOnce(Key) -> T>(&mut self, f: F) -> Key {
+        let key = match self.free_head {
+            Some(index) => {
+                let slot = &self.slots[index as usize];
+                Key {
+                    index,
+                    generation: slot.generation,
+                }
+            }
+            None => {
+                let index = u32::try_from(self.slots.len()).expect("too many slots");
+                Key {
+                    index,
+                    generation: 0,
+                }
+            }
+        };
+
+        let value = f(key);
+        if key.index() == self.slots.len() {
+            self.slots.push(Slot {
+                generation: 0,
+                entry: Entry::Occupied(value),
+            });
+        } else {
+            let slot = &mut self.slots[key.index()];
+            if let Entry::Vacant { next_free } = slot.entry {
+                self.free_head = next_free;
+            }
+            slot.entry = Entry::Occupied(value);
+        }
+        self.len += 1;
+        key
+    }
+
+    pub fn remove(&mut self, key: Key) -> Option<T> {
+        if !self.contains_key(key) {
+            return None;
+        }
+
+        let slot = &mut self.slots[key.index()];
+        let entry = mem::replace(
+            &mut slot.entry,
+            Entry::Vacant {
+                next_free: self.free_head,
+            },
+        );
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_head = Some(key.index);
+        self.len -= 1;
+
+        match entry {
+            Entry::Occupied(value) => Some(value),
+            Entry::Vacant { .. } => unreachable!(),
+        }
+    }
+
+    pub fn contains_key(&self, key: Key) -> bool {
+        self.get(key).is_some()
+    }
+
+    pub fn get(&self, key: Key) -> Option<&T> {
+        match self.slots.get(key.index()) {
+            Some(Slot {
+                generation,
+                entry: Entry::Occupied(value),
+            }) if *generation == key.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: Key) -> Option<&mut T> {
+        match self.slots.get_mut(key.index()) {
+            Some(Slot {
+                generation,
+                entry: Entry::Occupied(value),
+            }) if *generation == key.generation => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn retain<F: FnMut(Key, &mut T) -> bool>(&mut self, mut f: F) {
+        let keys: Vec<Key> = self.keys().collect();
+        for key in keys {
+            if !f(key, self.get_mut(key).unwrap()) {
+                self.remove(key);
+            }
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.retain(|_, _| false);
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            slots: self.slots.iter().enumerate(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            slots: self.slots.iter_mut().enumerate(),
+        }
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = Key> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.iter().map(|(_, value)| value)
+    }
+}
+
+impl<T> Default for SlotMap<T> {
+    fn default() -> SlotMap<T> {
+        SlotMap::new()
+    }
+}
+
+impl<T> Index<Key> for SlotMap<T> {
+    type Output = T;
+
+    fn index(&self, key: Key) -> &T {
+        self.get(key).expect("invalid key")
+    }
+}
+
+impl<T> IndexMut<Key> for SlotMap<T> {
+    fn index_mut(&mut self, key: Key) -> &mut T {
+        self.get_mut(key).expect("invalid key")
+    }
+}
+
+// Iterates live entries in slot order, which is insertion order as long as
+// no slot has been reused.
+pub struct Iter<'a, T> {
+    slots: std::iter::Enumerate<std::slice::Iter<'a, Slot<T>>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = (Key, &'a T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, slot) in self.slots.by_ref() {
+            if let Entry::Occupied(value) = &slot.entry {
+                let key = Key {
+                    index: index as u32,
+                    generation: slot.generation,
+                };
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+pub struct IterMut<'a, T> {
+    slots: std::iter::Enumerate<std::slice::IterMut<'a, Slot<T>>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = (Key, &'a mut T);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (index, slot) in self.slots.by_ref() {
+            if let Entry::Occupied(value) = &mut slot.entry {
+                let key = Key {
+                    index: index as u32,
+                    generation: slot.generation,
+                };
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+impl<'a, T> IntoIterator for &'a SlotMap<T> {
+    type Item = (Key, &'a T);
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut SlotMap<T> {
+    type Item = (Key, &'a mut T);
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, SlotMap};
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = SlotMap::new();
+        let a = map.insert("a");
+        let b = map.insert("b");
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(a), Some(&"a"));
+        assert_eq!(map[b], "b");
+
+        map[b] = "B";
+        assert_eq!(map.remove(b), Some("B"));
+        assert_eq!(map.remove(b), None);
+        assert_eq!(map.get(b), None);
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn stale_keys() {
+        let mut map = SlotMap::new();
+        let old = map.insert(1);
+        map.remove(old);
+        let new = map.insert(2);
+
+        assert_eq!(old.index(), new.index());
+        assert_ne!(old, new);
+        assert!(!map.contains_key(old));
+        assert_eq!(map.get_mut(old), None);
+        assert_eq!(map[new], 2);
+    }
+
+    #[test]
+    fn reuses_freed_slots() {
+        let mut map = SlotMap::new();
+        let keys: Vec<Key> = (0..4).map(|n| map.insert(n)).collect();
+        map.remove(keys[1]);
+        map.remove(keys[3]);
+
+        assert_eq!(map.insert(10).index(), 3);
+        assert_eq!(map.insert(11).index(), 1);
+        assert_eq!(map.insert(12).index(), 4);
+        assert_eq!(map.len(), 5);
+    }
+
+    #[test]
+    fn iteration_in_slot_order() {
+        let mut map = SlotMap::new();
+        let keys: Vec<Key> = (0..5).map(|n| map.insert(n)).collect();
+        map.remove(keys[2]);
+
+        assert!(map.values().copied().eq([0, 1, 3, 4]));
+        assert!(map.keys().eq([keys[0], keys[1], keys[3], keys[4]]));
+
+        for (_, value) in &mut map {
+            *value *= 10;
+        }
+        assert!(map.iter().map(|(_, value)| *value).eq([0, 10, 30, 40]));
+    }
+
+    #[test]
+    fn self_referencing_nodes() {
+        struct Node {
+            me: Key,
+            next: Option<Key>,
+        }
+
+        let mut graph = SlotMap::new();
+        let a = graph.insert_with_key(|me| Node { me, next: None });
+        let b = graph.insert_with_key(|me| Node { me, next: Some(a) });
+        graph[a].next = Some(b);
+
+        assert_eq!(graph[a].me, a);
+        assert_eq!(graph[graph[a].next.unwrap()].me, b);
+        assert_eq!(graph[b].next, Some(a));
+    }
+
+    #[test]
+    fn retain() {
+        let mut map = SlotMap::new();
+        (0..10).for_each(|n| {
+            map.insert(n);
+        });
+        map.retain(|_, value| *value % 2 == 0);
+        assert!(map.values().copied().eq([0, 2, 4, 6, 8]));
+        map.clear();
+        assert!(map.is_empty());
+    }
+}
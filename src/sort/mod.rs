@@ -0,0 +1,776 @@
+#![allow(dead_code)]
+
+use std::{
+    cmp::Ordering,
+    mem::{self, ManuallyDrop},
+    ptr,
+};
+
+use crate::vec::Vec;
+
+// slices up to this length are handed to insertion sort
+const INSERTION_THRESHOLD: usize = 20;
+
+pub fn insertion_sort<T: Ord>(v: &mut [T]) {
+    insertion_sort_impl(v, 1, &mut |a: &T, b: &T| a.lt(b));
+}
+
+pub fn insertion_sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
+    insertion_sort_impl(v, 1, &mut |a: &T, b: &T| compare(a, b) == Ordering::Less);
+}
+
+pub fn insertion_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) {
+    insertion_sort_impl(v, 1, &mut |a: &T, b: &T| f(a).lt(&f(b)));
+}
+
+pub fn merge_sort<T: Ord>(v: &mut [T]) {
+    merge_sort_impl(v, &mut |a: &T, b: &T| a.lt(b));
+}
+
+pub fn merge_sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
+    merge_sort_impl(v, &mut |a: &T, b: &T| compare(a, b) == Ordering::Less);
+}
+
+pub fn merge_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) {
+    merge_sort_impl(v, &mut |a: &T, b: &T| f(a).lt(&f(b)));
+}
+
+pub fn heap_sort<T: Ord>(v: &mut [T]) {
+    heap_sort_impl(v, &mut |a: &T, b: &T| a.lt(b));
+}
+
+pub fn heap_sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
+    heap_sort_impl(v, &mut |a: &T, b: &T| compare(a, b) == Ordering::Less);
+}
+
+pub fn heap_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) {
+    heap_sort_impl(v, &mut |a: &T, b: &T| f(a).lt(&f(b)));
+}
+
+pub fn intro_sort<T: Ord>(v: &mut [T]) {
+    intro_sort_impl(v, &mut |a: &T, b: &T| a.lt(b));
+}
+
+pub fn intro_sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
+    intro_sort_impl(v, &mut |a: &T, b: &T| compare(a, b) == Ordering::Less);
+}
+
+pub fn intro_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) {
+    intro_sort_impl(v, &mut |a: &T, b: &T| f(a).lt(&f(b)));
+}
+
+pub fn tim_sort<T: Ord>(v: &mut [T]) {
+    tim_sort_impl(v, &mut |a: &T, b: &T| a.lt(b));
+}
+
+pub fn tim_sort_by<T, F: FnMut(&T, &T) -> Ordering>(v: &mut [T], mut compare: F) {
+    tim_sort_impl(v, &mut |a: &T, b: &T| compare(a, b) == Ordering::Less);
+}
+
+pub fn tim_sort_by_key<T, K: Ord, F: FnMut(&T) -> K>(v: &mut [T], mut f: F) {
+    tim_sort_impl(v, &mut |a: &T, b: &T| f(a).lt(&f(b)));
+}
+
+// Radix sort never compares two items, so there is no `radix_sort_by`.
+pub fn radix_sort<T: RadixKey>(v: &mut [T]) {
+    let sorted = lsd_passes(v.iter().copied().collect(), |item| *item);
+    v.copy_from_slice(&sorted);
+}
+
+pub fn radix_sort_by_key<T, K: RadixKey, F: FnMut(&T) -> K>(v: &mut [T], f: F) {
+    // keys are extracted before anything moves, so a panicking `f` leaves `v` untouched
+    let keyed = lsd_passes(v.iter().map(f).zip(0..).collect(), |(key, _)| *key);
+    let mut order: Vec<usize> = keyed.into_iter().map(|(_, index)| index).collect();
+
+    // `order[i]` is the index of the item that belongs at `i`, apply it cycle by cycle
+    for start in 0..v.len() {
+        let mut i = start;
+        loop {
+            let source = mem::replace(&mut order[i], i);
+            if source == start {
+                break;
+            }
+            v.swap(i, source);
+            i = source;
+        }
+    }
+}
+
+// one stable counting sort pass per key byte, skipping bytes that are the same for every item
+fn lsd_passes<E: Copy, K: RadixKey>(mut items: Vec<E>, key: impl Fn(&E) -> K) -> Vec<E> {
+    let mut scratch = items.clone();
+
+    for byte in 0..K::BYTES {
+        let mut counts = [0usize; 256];
+        for item in items.iter() {
+            counts[key(item).radix_byte(byte) as usize] += 1;
+        }
+        if counts.contains(&items.len()) {
+            continue;
+        }
+
+        let mut offset = 0;
+        for count in counts.iter_mut() {
+            offset += mem::replace(count, offset);
+        }
+        for item in items.iter() {
+            let bucket = &mut counts[key(item).radix_byte(byte) as usize];
+            scratch[*bucket] = *item;
+            *bucket += 1;
+        }
+        mem::swap(&mut items, &mut scratch);
+    }
+
+    items
+}
+
+pub trait RadixKey: Copy {
+    const BYTES: usize;
+
+    // byte 0 is the least significant one
+    fn radix_byte(self, byte: usize) -> u8;
+}
+
+macro_rules! radix_key_unsigned {
+    ($($ty:ty),*) => {$(
+        impl RadixKey for $ty {
+            const BYTES: usize = mem::size_of::<$ty>();
+
+            fn radix_byte(self, byte: usize) -> u8 {
+                (self >> (byte * 8)) as u8
+            }
+        }
+    )*};
+}
+
+macro_rules! radix_key_signed {
+    ($($ty:ty => $unsigned:ty),*) => {$(
+        impl RadixKey for $ty {
+            const BYTES: usize = mem::size_of::<$ty>();
+
+            fn radix_byte(self, byte: usize) -> u8 {
+                // flipping the sign bit orders negative numbers before positive ones
+                ((self as $unsigned) ^ (1 << (<$ty>::BITS - 1))).radix_byte(byte)
+            }
+        }
+    )*};
+}
+
+radix_key_unsigned!(u8, u16, u32, u64, u128, usize);
+radix_key_signed!(i8 => u8, i16 => u16, i32 => u32, i64 => u64, i128 => u128, isize => usize);
+
+fn insertion_sort_impl<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], sorted: usize, is_less: &mut F) {
+    for i in sorted.max(1)..v.len() {
+        insert_tail(&mut v[..=i], is_less);
+    }
+}
+
+// moves the last item of `v` left until `v` is sorted, assuming `v[..len - 1]` already is
+fn insert_tail<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) {
+    let i = v.len() - 1;
+    let p = v.as_mut_ptr();
+
+    unsafe {
+        if !is_less(&*p.add(i), &*p.add(i - 1)) {
+            return;
+        }
+
+        let tmp = ManuallyDrop::new(ptr::read(p.add(i)));
+        let mut hole = Hole {
+            src: &*tmp,
+            dest: p.add(i - 1),
+        };
+        ptr::copy_nonoverlapping(p.add(i - 1), p.add(i), 1);
+
+        for j in (0..i - 1).rev() {
+            if !is_less(&*tmp, &*p.add(j)) {
+                break;
+            }
+            ptr::copy_nonoverlapping(p.add(j), hole.dest, 1);
+            hole.dest = p.add(j);
+        }
+    }
+}
+
+// Writes the item taken out of the slice back into the gap when dropped, even
+// if a comparison panicked half way through.
+struct Hole<T> {
+    src: *const T,
+    dest: *mut T,
+}
+
+impl<T> Drop for Hole<T> {
+    fn drop(&mut self) {
+        unsafe { ptr::copy_nonoverlapping(self.src, self.dest, 1) };
+    }
+}
+
+fn merge_sort_impl<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) {
+    if mem::size_of::<T>() == 0 {
+        return;
+    }
+    if v.len() <= INSERTION_THRESHOLD {
+        insertion_sort_impl(v, 1, is_less);
+        return;
+    }
+
+    let mut buf: Vec<T> = Vec::new();
+    buf.reserve(v.len() / 2);
+    merge_sort_rec(v, buf.as_mut_ptr(), is_less);
+}
+
+fn merge_sort_rec<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], buf: *mut T, is_less: &mut F) {
+    let len = v.len();
+    if len <= INSERTION_THRESHOLD {
+        insertion_sort_impl(v, 1, is_less);
+        return;
+    }
+
+    let mid = len / 2;
+    merge_sort_rec(&mut v[..mid], buf, is_less);
+    merge_sort_rec(&mut v[mid..], buf, is_less);
+    if is_less(&v[mid], &v[mid - 1]) {
+        unsafe { merge(v, mid, buf, is_less) };
+    }
+}
+
+// Merges the sorted runs `v[..mid]` and `v[mid..]`. The shorter run is moved
+// into `buf`, which must have room for it.
+unsafe fn merge<T, F: FnMut(&T, &T) -> bool>(
+    v: &mut [T],
+    mid: usize,
+    buf: *mut T,
+    is_less: &mut F,
+) {
+    let len = v.len();
+    let v = v.as_mut_ptr();
+    let v_mid = v.add(mid);
+    let v_end = v.add(len);
+
+    let mut hole;
+    if mid <= len - mid {
+        ptr::copy_nonoverlapping(v, buf, mid);
+        hole = MergeHole {
+            start: buf,
+            end: buf.add(mid),
+            dest: v,
+        };
+
+        let left = &mut hole.start;
+        let mut right = v_mid;
+        let out = &mut hole.dest;
+        while *left < hole.end && right < v_end {
+            // ties go to the left run to keep the sort stable
+            let src = if is_less(&*right, &**left) {
+                post_inc(&mut right)
+            } else {
+                post_inc(left)
+            };
+            ptr::copy_nonoverlapping(src, post_inc(out), 1);
+        }
+    } else {
+        ptr::copy_nonoverlapping(v_mid, buf, len - mid);
+        hole = MergeHole {
+            start: buf,
+            end: buf.add(len - mid),
+            dest: v_mid,
+        };
+
+        let left = &mut hole.dest;
+        let right = &mut hole.end;
+        let mut out = v_end;
+        while v < *left && buf < *right {
+            let src = if is_less(&*right.sub(1), &*left.sub(1)) {
+                pre_dec(left)
+            } else {
+                pre_dec(right)
+            };
+            ptr::copy_nonoverlapping(src, pre_dec(&mut out), 1);
+        }
+    }
+    // whatever is left in `buf` is copied back by the hole
+}
+
+unsafe fn post_inc<T>(ptr: &mut *mut T) -> *mut T {
+    let old = *ptr;
+    *ptr = ptr.add(1);
+    old
+}
+
+unsafe fn pre_dec<T>(ptr: &mut *mut T) -> *mut T {
+    *ptr = ptr.sub(1);
+    *ptr
+}
+
+struct MergeHole<T> {
+    start: *mut T,
+    end: *mut T,
+    dest: *mut T,
+}
+
+impl<T> Drop for MergeHole<T> {
+    fn drop(&mut self) {
+        unsafe {
+            let len = self.end.offset_from(self.start) as usize;
+            ptr::copy_nonoverlapping(self.start, self.dest, len);
+        }
+    }
+}
+
+fn heap_sort_impl<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) {
+    for node in (0..v.len() / 2).rev() {
+        sift_down(v, node, is_less);
+    }
+    for end in (1..v.len()).rev() {
+        v.swap(0, end);
+        sift_down(&mut v[..end], 0, is_less);
+    }
+}
+
+fn sift_down<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], mut node: usize, is_less: &mut F) {
+    loop {
+        let mut child = 2 * node + 1;
+        if child >= v.len() {
+            break;
+        }
+        if child + 1 < v.len() && is_less(&v[child], &v[child + 1]) {
+            child += 1;
+        }
+        if !is_less(&v[node], &v[child]) {
+            break;
+        }
+        v.swap(node, child);
+        node = child;
+    }
+}
+
+fn intro_sort_impl<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) {
+    // past 2 * log2(n) levels of partitioning quicksort has gone quadratic
+    let limit = 2 * (usize::BITS - v.len().leading_zeros());
+    quick_sort(v, limit, is_less);
+}
+
+fn quick_sort<T, F: FnMut(&T, &T) -> bool>(mut v: &mut [T], mut limit: u32, is_less: &mut F) {
+    loop {
+        if v.len() <= INSERTION_THRESHOLD {
+            insertion_sort_impl(v, 1, is_less);
+            return;
+        }
+        if limit == 0 {
+            heap_sort_impl(v, is_less);
+            return;
+        }
+        limit -= 1;
+
+        let pivot = median_of_three(v, is_less);
+        v.swap(0, pivot);
+        let mid = partition(v, is_less);
+
+        // recurse into the smaller side so the stack stays O(log n)
+        let (left, right) = mem::take(&mut v).split_at_mut(mid);
+        let right = &mut right[1..];
+        if left.len() < right.len() {
+            quick_sort(left, limit, is_less);
+            v = right;
+        } else {
+            quick_sort(right, limit, is_less);
+            v = left;
+        }
+    }
+}
+
+fn median_of_three<T, F: FnMut(&T, &T) -> bool>(v: &[T], is_less: &mut F) -> usize {
+    let (mut a, b, mut c) = (v.len() / 4, v.len() / 2, v.len() / 4 * 3);
+    if is_less(&v[c], &v[a]) {
+        mem::swap(&mut a, &mut c);
+    }
+    if is_less(&v[b], &v[a]) {
+        a
+    } else if is_less(&v[c], &v[b]) {
+        c
+    } else {
+        b
+    }
+}
+
+// Partitions `v[1..]` around the pivot in `v[0]` and moves the pivot between
+// the two halves, returning its final index.
+fn partition<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) -> usize {
+    let (pivot, rest) = v.split_at_mut(1);
+    let pivot = &pivot[0];
+
+    let (mut l, mut r) = (0, rest.len());
+    loop {
+        while l < r && is_less(&rest[l], pivot) {
+            l += 1;
+        }
+        while l < r && !is_less(&rest[r - 1], pivot) {
+            r -= 1;
+        }
+        if l >= r {
+            break;
+        }
+        r -= 1;
+        rest.swap(l, r);
+        l += 1;
+    }
+
+    v.swap(0, l);
+    l
+}
+
+#[derive(Clone, Copy)]
+struct Run {
+    start: usize,
+    len: usize,
+}
+
+fn tim_sort_impl<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) {
+    if mem::size_of::<T>() == 0 {
+        return;
+    }
+    let len = v.len();
+    if len <= INSERTION_THRESHOLD {
+        insertion_sort_impl(v, 1, is_less);
+        return;
+    }
+
+    let min_run = min_run_length(len);
+    let mut buf: Vec<T> = Vec::new();
+    buf.reserve(len / 2);
+    let mut runs: Vec<Run> = Vec::new();
+
+    let mut start = 0;
+    while start < len {
+        let mut run_len = find_run(&mut v[start..], is_less);
+        if run_len < min_run {
+            let end = (start + min_run).min(len);
+            insertion_sort_impl(&mut v[start..end], run_len, is_less);
+            run_len = end - start;
+        }
+        runs.push(Run {
+            start,
+            len: run_len,
+        });
+        start += run_len;
+
+        while let Some(r) = collapse(&runs, len) {
+            let (left, right) = (runs[r], runs[r + 1]);
+            let merged = &mut v[left.start..right.start + right.len];
+            unsafe { merge(merged, left.len, buf.as_mut_ptr(), is_less) };
+            runs[r] = Run {
+                start: left.start,
+                len: left.len + right.len,
+            };
+            runs.remove(r + 1);
+        }
+    }
+}
+
+// same choice as the reference implementation: a value in 32..=64 such that
+// len / min_run is a power of two or slightly less
+fn min_run_length(mut len: usize) -> usize {
+    let mut r = 0;
+    while len >= 64 {
+        r |= len & 1;
+        len >>= 1;
+    }
+    len + r
+}
+
+// Returns the length of the ascending run at the start of `v`, reversing it
+// first if it is strictly descending.
+fn find_run<T, F: FnMut(&T, &T) -> bool>(v: &mut [T], is_less: &mut F) -> usize {
+    if v.len() < 2 {
+        return v.len();
+    }
+
+    let mut end = 2;
+    if is_less(&v[1], &v[0]) {
+        while end < v.len() && is_less(&v[end], &v[end - 1]) {
+            end += 1;
+        }
+        v[..end].reverse();
+    } else {
+        while end < v.len() && !is_less(&v[end], &v[end - 1]) {
+            end += 1;
+        }
+    }
+    end
+}
+
+// Picks the pair of runs to merge next so run lengths keep decreasing at
+// least as fast as the Fibonacci numbers, or merges everything once the last
+// run reaches the end of the slice.
+fn collapse(runs: &[Run], stop: usize) -> Option<usize> {
+    let n = runs.len();
+    if n >= 2
+        && (runs[n - 1].start + runs[n - 1].len == stop
+            || runs[n - 2].len <= runs[n - 1].len
+            || (n >= 3 && runs[n - 3].len <= runs[n - 2].len + runs[n - 1].len)
+            || (n >= 4 && runs[n - 4].len <= runs[n - 3].len + runs[n - 2].len))
+    {
+        if n >= 3 && runs[n - 3].len < runs[n - 1].len {
+            Some(n - 3)
+        } else {
+            Some(n - 2)
+        }
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cell::Cell,
+        cmp::Ordering,
+        panic::{self, AssertUnwindSafe},
+        rc::Rc,
+        time::Instant,
+    };
+
+    use super::*;
+    use crate::vec::Vec;
+
+    type SortBy = fn(&mut [(u32, usize)], &mut dyn FnMut(&(u32, usize), &(u32, usize)) -> Ordering);
+
+    const STABLE: [(&str, SortBy); 3] = [
+        ("insertion", |v, f| insertion_sort_by(v, f)),
+        ("merge", |v, f| merge_sort_by(v, f)),
+        ("tim", |v, f| tim_sort_by(v, f)),
+    ];
+
+    const UNSTABLE: [(&str, SortBy); 2] = [
+        ("heap", |v, f| heap_sort_by(v, f)),
+        ("intro", |v, f| intro_sort_by(v, f)),
+    ];
+
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+    }
+
+    fn inputs() -> std::vec::Vec<Vec<u32>> {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+        let mut inputs = std::vec::Vec::new();
+        for len in [0, 1, 2, 3, 19, 20, 21, 64, 65, 200, 1000] {
+            inputs.push((0..len).map(|_| rng.next() as u32).collect());
+            inputs.push((0..len).map(|_| rng.next() as u32 % 4).collect());
+            inputs.push((0..len).collect());
+            inputs.push((0..len).rev().collect());
+            let mut sawtooth: Vec<u32> = (0..len).map(|n| n % 37).collect();
+            sawtooth.extend((0..len / 3).rev());
+            inputs.push(sawtooth);
+        }
+        inputs
+    }
+
+    #[test]
+    fn sorts_like_std() {
+        for input in inputs() {
+            let mut expected = input.clone();
+            expected.sort();
+
+            for (name, sort) in STABLE.iter().chain(UNSTABLE.iter()) {
+                let mut v: Vec<(u32, usize)> = input.iter().map(|&n| (n, 0)).collect();
+                sort(&mut v, &mut |a, b| a.0.cmp(&b.0));
+                assert!(
+                    v.iter().map(|item| item.0).eq(expected.iter().copied()),
+                    "{}",
+                    name
+                );
+            }
+
+            let mut v = input.clone();
+            radix_sort(&mut v);
+            assert_eq!(v, expected);
+        }
+    }
+
+    #[test]
+    fn ord_and_key_variants() {
+        let input = Vec::from([5, -3, 2, 9, -7, 0, 2]);
+        let expected = [-7, -3, 0, 2, 2, 5, 9];
+        let descending = [9, 5, 2, 2, 0, -3, -7];
+
+        let sorts: [fn(&mut [i32]); 6] = [
+            insertion_sort,
+            merge_sort,
+            heap_sort,
+            intro_sort,
+            tim_sort,
+            radix_sort,
+        ];
+        for sort in sorts {
+            let mut v = input.clone();
+            sort(&mut v);
+            assert_eq!(v, expected);
+        }
+
+        type SortByKey = fn(&mut [i32], fn(&i32) -> i64);
+        let by_key: [SortByKey; 6] = [
+            insertion_sort_by_key,
+            merge_sort_by_key,
+            heap_sort_by_key,
+            intro_sort_by_key,
+            tim_sort_by_key,
+            radix_sort_by_key,
+        ];
+        for sort in by_key {
+            let mut v = input.clone();
+            sort(&mut v, |n| -(*n as i64));
+            assert_eq!(v, descending);
+        }
+    }
+
+    #[test]
+    fn stable() {
+        for input in inputs() {
+            let tagged: Vec<(u32, usize)> =
+                input.iter().enumerate().map(|(i, &n)| (n % 8, i)).collect();
+            let mut expected = tagged.clone();
+            expected.sort_by_key(|item| item.0);
+
+            for (name, sort) in STABLE.iter() {
+                let mut v = tagged.clone();
+                sort(&mut v, &mut |a, b| a.0.cmp(&b.0));
+                assert_eq!(v, expected, "{}", name);
+            }
+
+            let mut v = tagged.clone();
+            radix_sort_by_key(&mut v, |item| item.0);
+            assert_eq!(v, expected);
+        }
+    }
+
+    #[derive(Clone)]
+    struct Tracked {
+        key: u32,
+        id: usize,
+        drops: Rc<Cell<usize>>,
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) {
+            self.drops.set(self.drops.get() + 1);
+        }
+    }
+
+    fn tracked(len: usize, drops: &Rc<Cell<usize>>) -> Vec<Tracked> {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+        (0..len)
+            .map(|id| Tracked {
+                key: rng.next() as u32 % 100,
+                id,
+                drops: drops.clone(),
+            })
+            .collect()
+    }
+
+    type SortTracked = fn(&mut [Tracked], &mut dyn FnMut(&Tracked, &Tracked) -> Ordering);
+
+    const ALL_TRACKED: [(&str, SortTracked); 5] = [
+        ("insertion", |v, f| insertion_sort_by(v, f)),
+        ("merge", |v, f| merge_sort_by(v, f)),
+        ("tim", |v, f| tim_sort_by(v, f)),
+        ("heap", |v, f| heap_sort_by(v, f)),
+        ("intro", |v, f| intro_sort_by(v, f)),
+    ];
+
+    #[test]
+    fn drops_each_item_once() {
+        for (name, sort) in ALL_TRACKED {
+            let drops = Rc::new(Cell::new(0));
+            let mut v = tracked(500, &drops);
+            sort(&mut v, &mut |a, b| a.key.cmp(&b.key));
+            assert_eq!(drops.get(), 0, "{}", name);
+            drop(v);
+            assert_eq!(drops.get(), 500, "{}", name);
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let mut v = tracked(500, &drops);
+        radix_sort_by_key(&mut v, |item| item.key);
+        assert!(v.windows(2).all(|w| w[0].key <= w[1].key));
+        drop(v);
+        assert_eq!(drops.get(), 500);
+    }
+
+    #[test]
+    fn panic_in_comparison_keeps_every_item() {
+        for (name, sort) in ALL_TRACKED {
+            for panic_after in [0, 1, 10, 100, 1000, 3000] {
+                let drops = Rc::new(Cell::new(0));
+                let mut v = tracked(300, &drops);
+                let mut comparisons = 0;
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    sort(&mut v, &mut |a, b| {
+                        comparisons += 1;
+                        if comparisons > panic_after {
+                            panic!("comparison {}", comparisons);
+                        }
+                        a.key.cmp(&b.key)
+                    })
+                }));
+
+                let mut ids: std::vec::Vec<usize> = v.iter().map(|item| item.id).collect();
+                ids.sort();
+                assert!(ids.into_iter().eq(0..300), "{} {}", name, panic_after);
+                assert_eq!(drops.get(), 0, "{} {}", name, panic_after);
+                if result.is_ok() {
+                    assert!(v.windows(2).all(|w| w[0].key <= w[1].key));
+                }
+                drop(v);
+                assert_eq!(drops.get(), 300, "{} {}", name, panic_after);
+            }
+        }
+
+        let drops = Rc::new(Cell::new(0));
+        let mut v = tracked(300, &drops);
+        let mut calls = 0;
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            radix_sort_by_key(&mut v, |item| {
+                calls += 1;
+                if calls > 100 {
+                    panic!("key {}", calls);
+                }
+                item.key
+            })
+        }));
+        assert!(result.is_err());
+        assert!(v.iter().map(|item| item.id).eq(0..300));
+    }
+
+    // cargo test --release -- --ignored --nocapture bench
+    #[test]
+    #[ignore]
+    fn bench_against_std() {
+        let mut rng = Rng(0x853c_49e6_748f_ea9b);
+        let input: Vec<u64> = (0..1_000_000).map(|_| rng.next()).collect();
+
+        type Sort = fn(&mut [u64]);
+        let sorts: [(&str, Sort); 7] = [
+            ("slice::sort", |v| v.sort()),
+            ("slice::sort_unstable", |v| v.sort_unstable()),
+            ("merge_sort", merge_sort),
+            ("tim_sort", tim_sort),
+            ("intro_sort", intro_sort),
+            ("heap_sort", heap_sort),
+            ("radix_sort", radix_sort),
+        ];
+        for (name, sort) in sorts {
+            let mut v = input.clone();
+            let start = Instant::now();
+            sort(&mut v);
+            println!("{:>24}: {:?}", name, start.elapsed());
+            assert!(v.windows(2).all(|w| w[0] <= w[1]));
+        }
+    }
+}
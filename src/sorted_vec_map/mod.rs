@@ -0,0 +1,609 @@
+#![allow(dead_code)]
+
+use std::{
+    borrow::Borrow,
+    cmp::Ordering,
+    fmt,
+    iter::FromIterator,
+    mem,
+    ops::{Bound, Index, RangeBounds},
+    slice,
+};
+
+use crate::{sort, vec::Vec};
+
+// Entries kept sorted by key in one `vec::Vec`. Lookups are binary searches
+// over contiguous memory, which beats a pointer-chasing tree for small or
+// read-mostly maps; inserting and removing shift the tail, so they are O(n).
+// Building from an iterator sorts once instead of inserting one at a time.
+pub struct SortedVecMap<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K, V> SortedVecMap<K, V> {
+    pub fn new() -> SortedVecMap<K, V> {
+        SortedVecMap {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn with_capacity(cap: usize) -> SortedVecMap<K, V> {
+        let mut map = SortedVecMap::new();
+        map.entries.reserve(cap);
+        map
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.len() == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.entries.capacity()
+    }
+
+    // the entries in key order
+    pub fn as_slice(&self) -> &[(K, V)] {
+        &self.entries
+    }
+
+    pub fn clear(&mut self) {
+        while self.entries.pop().is_some() {}
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.entries.first().map(|(key, value)| (key, value))
+    }
+
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        self.entries.last().map(|(key, value)| (key, value))
+    }
+
+    pub fn pop_first(&mut self) -> Option<(K, V)> {
+        if self.is_empty() {
+            None
+        } else {
+            Some(self.entries.remove(0))
+        }
+    }
+
+    pub fn pop_last(&mut self) -> Option<(K, V)> {
+        self.entries.pop()
+    }
+
+    // compacts in place, so a panicking `f` leaves the unvisited entries
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut f: F) {
+        let mut retain = Retain {
+            entries: &mut self.entries,
+            kept: 0,
+            visited: 0,
+        };
+        while retain.visited < retain.entries.len() {
+            let (key, value) = &mut retain.entries[retain.visited];
+            if f(key, value) {
+                retain.entries.swap(retain.kept, retain.visited);
+                retain.kept += 1;
+            }
+            retain.visited += 1;
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            entries: self.entries.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            entries: self.entries.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &K> + ExactSizeIterator {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &V> + ExactSizeIterator {
+        self.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut V> + ExactSizeIterator {
+        self.iter_mut().map(|(_, value)| value)
+    }
+}
+
+impl<K: Ord, V> SortedVecMap<K, V> {
+    // Sorts the entries once and keeps the last value given for each key,
+    // like inserting them one after another would.
+    pub fn from_vec(entries: Vec<(K, V)>) -> SortedVecMap<K, V> {
+        let mut map = SortedVecMap { entries };
+        map.sort_and_dedup();
+        map
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.search(&key) {
+            Ok(index) => Some(mem::replace(&mut self.entries[index].1, value)),
+            Err(index) => {
+                self.entries.insert(index, (key, value));
+                None
+            }
+        }
+    }
+
+    // leaves an existing entry alone; returns whether the key was newly added
+    pub fn insert_if_absent(&mut self, key: K, value: V) -> bool {
+        match self.search(&key) {
+            Ok(_) => false,
+            Err(index) => {
+                self.entries.insert(index, (key, value));
+                true
+            }
+        }
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get_key_value(key).map(|(_, value)| value)
+    }
+
+    pub fn get_key_value<Q>(&self, key: &Q) -> Option<(&K, &V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.search(key).ok()?;
+        let (key, value) = &self.entries[index];
+        Some((key, value))
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.search(key).ok()?;
+        Some(&mut self.entries[index].1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.search(key).is_ok()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    pub fn remove_entry<Q>(&mut self, key: &Q) -> Option<(K, V)>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let index = self.search(key).ok()?;
+        Some(self.entries.remove(index))
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, K, V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        let start = match range.start_bound() {
+            Bound::Included(start) => self.partition(|key| key < start),
+            Bound::Excluded(start) => self.partition(|key| key <= start),
+            Bound::Unbounded => 0,
+        };
+        let end = match range.end_bound() {
+            Bound::Included(end) => self.partition(|key| key <= end),
+            Bound::Excluded(end) => self.partition(|key| key < end),
+            Bound::Unbounded => self.len(),
+        };
+        Iter {
+            entries: self.entries[start..end.max(start)].iter(),
+        }
+    }
+
+    fn search<Q>(&self, key: &Q) -> Result<usize, usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.entries
+            .binary_search_by(|(other, _)| other.borrow().cmp(key))
+    }
+
+    // index of the first entry whose key fails `pred`
+    fn partition<Q, F>(&self, mut pred: F) -> usize
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+        F: FnMut(&Q) -> bool,
+    {
+        self.entries.partition_point(|(key, _)| pred(key.borrow()))
+    }
+
+    fn sort_and_dedup(&mut self) {
+        // stable, so of several entries with the same key the last one given
+        // ends up last and is the one kept
+        sort::tim_sort_by(&mut self.entries, |(a, _), (b, _)| a.cmp(b));
+
+        // compacted in place like `retain`, in case a key's `==` panics
+        let mut dedup = Retain {
+            entries: &mut self.entries,
+            kept: 0,
+            visited: 0,
+        };
+        while dedup.visited < dedup.entries.len() {
+            let (kept, visited) = (dedup.kept, dedup.visited);
+            if kept > 0 && dedup.entries[kept - 1].0 == dedup.entries[visited].0 {
+                // the later entry takes the earlier one's place, which is dropped
+                dedup.entries.swap(kept - 1, visited);
+            } else {
+                dedup.entries.swap(kept, visited);
+                dedup.kept += 1;
+            }
+            dedup.visited += 1;
+        }
+    }
+}
+
+// Entries before `kept` stay, those up to `visited` were rejected, and the
+// rest weren't looked at (only if a callback panicked). Shifting the rest
+// down keeps them sorted.
+struct Retain<'a, K, V> {
+    entries: &'a mut Vec<(K, V)>,
+    kept: usize,
+    visited: usize,
+}
+
+impl<K, V> Drop for Retain<'_, K, V> {
+    fn drop(&mut self) {
+        for index in self.visited..self.entries.len() {
+            self.entries.swap(self.kept + index - self.visited, index);
+        }
+        for _ in self.kept..self.visited {
+            self.entries.pop();
+        }
+    }
+}
+
+impl<K, V> Default for SortedVecMap<K, V> {
+    fn default() -> SortedVecMap<K, V> {
+        SortedVecMap::new()
+    }
+}
+
+impl<K: Clone, V: Clone> Clone for SortedVecMap<K, V> {
+    fn clone(&self) -> SortedVecMap<K, V> {
+        SortedVecMap {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for SortedVecMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: PartialEq, V: PartialEq> PartialEq for SortedVecMap<K, V> {
+    fn eq(&self, other: &SortedVecMap<K, V>) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for SortedVecMap<K, V> {}
+
+impl<K: PartialOrd, V: PartialOrd> PartialOrd for SortedVecMap<K, V> {
+    fn partial_cmp(&self, other: &SortedVecMap<K, V>) -> Option<Ordering> {
+        self.entries.partial_cmp(&other.entries)
+    }
+}
+
+impl<K: Ord, V: Ord> Ord for SortedVecMap<K, V> {
+    fn cmp(&self, other: &SortedVecMap<K, V>) -> Ordering {
+        self.entries.cmp(&other.entries)
+    }
+}
+
+impl<K, Q, V> Index<&Q> for SortedVecMap<K, V>
+where
+    K: Ord + Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("key not found")
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for SortedVecMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> SortedVecMap<K, V> {
+        SortedVecMap::from_vec(iter.into_iter().collect())
+    }
+}
+
+// appends everything and re-sorts once; the new entries come after the
+// existing ones, so they win on equal keys
+impl<K: Ord, V> Extend<(K, V)> for SortedVecMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+        self.sort_and_dedup();
+    }
+}
+
+impl<K: Ord, V, const N: usize> From<[(K, V); N]> for SortedVecMap<K, V> {
+    fn from(entries: [(K, V); N]) -> SortedVecMap<K, V> {
+        SortedVecMap::from_vec(Vec::from(entries))
+    }
+}
+
+impl<K, V> From<SortedVecMap<K, V>> for Vec<(K, V)> {
+    fn from(map: SortedVecMap<K, V>) -> Vec<(K, V)> {
+        map.entries
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    entries: slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a V)> {
+        self.entries.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+impl<K, V> Clone for Iter<'_, K, V> {
+    fn clone(&self) -> Self {
+        Iter {
+            entries: self.entries.clone(),
+        }
+    }
+}
+
+pub struct IterMut<'a, K, V> {
+    entries: slice::IterMut<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<(&'a K, &'a mut V)> {
+        self.entries.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.entries.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for IterMut<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.entries.next_back().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+
+impl<K, V> IntoIterator for SortedVecMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = crate::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> crate::vec::IntoIter<(K, V)> {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a SortedVecMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+impl<'a, K, V> IntoIterator for &'a mut SortedVecMap<K, V> {
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> IterMut<'a, K, V> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        cmp::Ordering,
+        collections::BTreeMap,
+        panic::{self, AssertUnwindSafe},
+    };
+
+    use super::SortedVecMap;
+    use crate::vec::Vec;
+
+    #[test]
+    fn insert_get_remove() {
+        let mut map = SortedVecMap::new();
+        assert_eq!(map.insert(String::from("b"), 2), None);
+        assert_eq!(map.insert(String::from("a"), 1), None);
+        assert_eq!(map.insert(String::from("c"), 3), None);
+        assert_eq!(map.insert(String::from("b"), 20), Some(2));
+        assert!(!map.insert_if_absent(String::from("b"), 200));
+        assert!(map.insert_if_absent(String::from("bb"), 4));
+        assert_eq!(map.remove("bb"), Some(4));
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get("b"), Some(&20));
+        assert_eq!(map["a"], 1);
+        assert!(map.contains_key("c"));
+        *map.get_mut("c").unwrap() += 1;
+        assert!(map.keys().eq(["a", "b", "c"]));
+
+        assert_eq!(map.remove("a"), Some(1));
+        assert_eq!(map.remove("a"), None);
+        assert_eq!(map.remove_entry("c"), Some((String::from("c"), 4)));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn bulk_construction() {
+        let map: SortedVecMap<u32, &str> = [(3, "c"), (1, "a"), (3, "C"), (2, "b"), (1, "A")]
+            .into_iter()
+            .collect();
+        assert_eq!(map.as_slice(), [(1, "A"), (2, "b"), (3, "C")]);
+
+        let mut map = SortedVecMap::from([(5, 'e'), (1, 'a')]);
+        map.extend([(3, 'c'), (5, 'E')]);
+        assert_eq!(map.as_slice(), [(1, 'a'), (3, 'c'), (5, 'E')]);
+        assert_eq!(Vec::from(map), [(1, 'a'), (3, 'c'), (5, 'E')]);
+    }
+
+    #[test]
+    fn navigation() {
+        let mut map: SortedVecMap<u32, u32> = (0..10).map(|n| (n * 10, n)).collect();
+        assert_eq!(map.first_key_value(), Some((&0, &0)));
+        assert_eq!(map.last_key_value(), Some((&90, &9)));
+
+        let keys = |iter: super::Iter<'_, u32, u32>| iter.map(|(&k, _)| k).collect::<Vec<_>>();
+        assert_eq!(keys(map.range(20..50)), [20, 30, 40]);
+        assert_eq!(keys(map.range(15..=50)), [20, 30, 40, 50]);
+        assert_eq!(keys(map.range(..20)), [0, 10]);
+        assert_eq!(keys(map.range(85..)), [90]);
+        assert_eq!(keys(map.range(41..45)), []);
+        assert!(map
+            .range::<u32, _>(..)
+            .rev()
+            .take(2)
+            .map(|(&k, _)| k)
+            .eq([90, 80]));
+
+        let bounds = (std::ops::Bound::Excluded(20), std::ops::Bound::Excluded(50));
+        assert_eq!(keys(map.range(bounds)), [30, 40]);
+
+        assert_eq!(map.pop_first(), Some((0, 0)));
+        assert_eq!(map.pop_last(), Some((90, 9)));
+        assert_eq!(map.len(), 8);
+    }
+
+    #[test]
+    fn against_btree_map() {
+        let mut map = SortedVecMap::new();
+        let mut expected = BTreeMap::new();
+
+        for step in 0..5000u32 {
+            let key = (step * 7919) % 307;
+            if step % 3 == 0 {
+                assert_eq!(map.remove(&key), expected.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, step), expected.insert(key, step));
+            }
+            if step % 101 == 0 {
+                let (low, high) = (key / 2, key);
+                assert!(map.range(low..high).eq(expected.range(low..high)));
+            }
+        }
+
+        assert!(map.iter().eq(expected.iter()));
+        map.retain(|k, _| k % 2 == 0);
+        expected.retain(|k, _| k % 2 == 0);
+        assert!(map.iter().eq(expected.iter()));
+    }
+
+    #[test]
+    fn iteration() {
+        let mut map: SortedVecMap<u32, u32> = (0..5).rev().map(|n| (n, n)).collect();
+        for (_, value) in &mut map {
+            *value *= 2;
+        }
+        map.values_mut().for_each(|value| *value += 1);
+        assert!(map.values().copied().eq([1, 3, 5, 7, 9]));
+        assert!(map
+            .clone()
+            .into_iter()
+            .eq(map.iter().map(|(&k, &v)| (k, v))));
+        assert!(map < SortedVecMap::from([(0, 2)]));
+        assert_eq!(
+            format!("{:?}", SortedVecMap::from([(2, 'b'), (1, 'a')])),
+            "{1: 'a', 2: 'b'}"
+        );
+    }
+
+    // ordered by the number, but comparing 3 for equality panics
+    #[derive(Debug, Eq)]
+    struct Touchy(u32);
+
+    impl PartialEq for Touchy {
+        fn eq(&self, other: &Touchy) -> bool {
+            assert!(self.0 != 3 && other.0 != 3, "compared 3");
+            self.0 == other.0
+        }
+    }
+
+    impl PartialOrd for Touchy {
+        fn partial_cmp(&self, other: &Touchy) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+
+    impl Ord for Touchy {
+        fn cmp(&self, other: &Touchy) -> Ordering {
+            self.0.cmp(&other.0)
+        }
+    }
+
+    #[test]
+    fn survives_panicking_callbacks() {
+        let mut map: SortedVecMap<u32, u32> = (0..10).map(|n| (n, n)).collect();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.retain(|&k, _| {
+                assert!(k < 6, "predicate failed");
+                k % 2 == 0
+            })
+        }));
+        assert!(result.is_err());
+        // whatever was visited is filtered, the rest is still there
+        assert!(map.keys().copied().eq([0, 2, 4, 6, 7, 8, 9]));
+
+        let mut map = SortedVecMap::new();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            map.extend((0..6).rev().map(|n| (Touchy(n), n)))
+        }));
+        assert!(result.is_err());
+        assert!(map.values().copied().eq(0..6));
+    }
+}
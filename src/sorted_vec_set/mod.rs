@@ -0,0 +1,249 @@
+#![allow(dead_code)]
+
+use std::{borrow::Borrow, fmt, iter::FromIterator, ops::RangeBounds};
+
+use crate::{
+    sorted_vec_map::{self, SortedVecMap},
+    vec::Vec,
+};
+
+pub struct SortedVecSet<T> {
+    map: SortedVecMap<T, ()>,
+}
+
+impl<T> SortedVecSet<T> {
+    pub fn new() -> SortedVecSet<T> {
+        SortedVecSet {
+            map: SortedVecMap::new(),
+        }
+    }
+
+    pub fn with_capacity(cap: usize) -> SortedVecSet<T> {
+        SortedVecSet {
+            map: SortedVecMap::with_capacity(cap),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.map.first_key_value().map(|(item, _)| item)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.map.last_key_value().map(|(item, _)| item)
+    }
+
+    pub fn pop_first(&mut self) -> Option<T> {
+        self.map.pop_first().map(|(item, _)| item)
+    }
+
+    pub fn pop_last(&mut self) -> Option<T> {
+        self.map.pop_last().map(|(item, _)| item)
+    }
+
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        self.map.retain(|item, _| f(item));
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            inner: self.map.iter(),
+        }
+    }
+}
+
+impl<T: Ord> SortedVecSet<T> {
+    // sorts and dedups once, keeping the last of several equal items
+    pub fn from_vec(items: Vec<T>) -> SortedVecSet<T> {
+        items.into_iter().collect()
+    }
+
+    // returns whether the item was newly added
+    pub fn insert(&mut self, item: T) -> bool {
+        self.map.insert_if_absent(item, ())
+    }
+
+    pub fn contains<Q>(&self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.contains_key(item)
+    }
+
+    pub fn get<Q>(&self, item: &Q) -> Option<&T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.get_key_value(item).map(|(item, _)| item)
+    }
+
+    pub fn remove<Q>(&mut self, item: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove(item).is_some()
+    }
+
+    pub fn take<Q>(&mut self, item: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.map.remove_entry(item).map(|(item, _)| item)
+    }
+
+    pub fn range<Q, R>(&self, range: R) -> Iter<'_, T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+        R: RangeBounds<Q>,
+    {
+        Iter {
+            inner: self.map.range(range),
+        }
+    }
+}
+
+impl<T> Default for SortedVecSet<T> {
+    fn default() -> SortedVecSet<T> {
+        SortedVecSet::new()
+    }
+}
+
+impl<T: Clone> Clone for SortedVecSet<T> {
+    fn clone(&self) -> SortedVecSet<T> {
+        SortedVecSet {
+            map: self.map.clone(),
+        }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for SortedVecSet<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: PartialEq> PartialEq for SortedVecSet<T> {
+    fn eq(&self, other: &SortedVecSet<T>) -> bool {
+        self.map == other.map
+    }
+}
+
+impl<T: Eq> Eq for SortedVecSet<T> {}
+
+impl<T: Ord> FromIterator<T> for SortedVecSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> SortedVecSet<T> {
+        SortedVecSet {
+            map: iter.into_iter().map(|item| (item, ())).collect(),
+        }
+    }
+}
+
+impl<T: Ord> Extend<T> for SortedVecSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        self.map.extend(iter.into_iter().map(|item| (item, ())));
+    }
+}
+
+impl<T: Ord, const N: usize> From<[T; N]> for SortedVecSet<T> {
+    fn from(items: [T; N]) -> SortedVecSet<T> {
+        items.into_iter().collect()
+    }
+}
+
+pub struct Iter<'a, T> {
+    inner: sorted_vec_map::Iter<'a, T, ()>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        self.inner.next().map(|(item, _)| item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(item, _)| item)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a SortedVecSet<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SortedVecSet;
+    use crate::vec::Vec;
+
+    #[test]
+    fn insert_contains_remove() {
+        let mut set = SortedVecSet::new();
+        [5, 3, 1, 6, 4].iter().for_each(|&item| {
+            assert!(set.insert(item));
+        });
+        assert!(!set.insert(3));
+        assert!([1, 3, 4, 5, 6].iter().all(|item| set.contains(item)));
+        assert!(!set.contains(&2));
+
+        assert!(set.remove(&4));
+        assert!(!set.remove(&4));
+        assert_eq!(set.take(&1), Some(1));
+        assert!(set.iter().copied().eq([3, 5, 6]));
+    }
+
+    #[test]
+    fn navigation() {
+        let mut set: SortedVecSet<i32> =
+            [3, 4, 8, 11, 1, 54, -5, 13, 0, 5, 8].into_iter().collect();
+        assert_eq!(set.len(), 10);
+        assert_eq!(set.first(), Some(&-5));
+        assert_eq!(set.last(), Some(&54));
+        assert!(set.range(4..12).copied().eq([4, 5, 8, 11]));
+        assert!(set.range(..=0).rev().copied().eq([0, -5]));
+
+        assert_eq!(set.pop_first(), Some(-5));
+        assert_eq!(set.pop_last(), Some(54));
+        set.retain(|n| n % 2 == 1);
+        assert_eq!(set, SortedVecSet::from([1, 3, 5, 11, 13]));
+    }
+
+    #[test]
+    fn bulk_construction() {
+        let words = Vec::from(["pear", "apple", "fig", "apple", "pear"]);
+        let mut set = SortedVecSet::from_vec(words);
+        assert!(set.iter().copied().eq(["apple", "fig", "pear"]));
+
+        set.extend(["kiwi", "fig"]);
+        assert_eq!(format!("{:?}", set), r#"{"apple", "fig", "kiwi", "pear"}"#);
+        assert_eq!(set.get("kiwi"), Some(&"kiwi"));
+    }
+}
@@ -0,0 +1,49 @@
+pub trait GrowthPolicy {
+    // called with the current capacity when a full vec needs room for one more item;
+    // results that don't exceed `cap` are bumped to `cap + 1`
+    fn next_capacity(&self, cap: usize) -> usize;
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Doubling;
+
+impl GrowthPolicy for Doubling {
+    fn next_capacity(&self, cap: usize) -> usize {
+        cap.saturating_mul(2)
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OneAndHalf;
+
+impl GrowthPolicy for OneAndHalf {
+    fn next_capacity(&self, cap: usize) -> usize {
+        cap.saturating_add(cap / 2)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedIncrement(pub usize);
+
+impl GrowthPolicy for FixedIncrement {
+    fn next_capacity(&self, cap: usize) -> usize {
+        cap.saturating_add(self.0)
+    }
+}
+
+// doubles until `threshold`, then grows by at most `max_step` at a time
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Capped {
+    pub threshold: usize,
+    pub max_step: usize,
+}
+
+impl GrowthPolicy for Capped {
+    fn next_capacity(&self, cap: usize) -> usize {
+        if cap < self.threshold {
+            cap.saturating_mul(2)
+        } else {
+            cap.saturating_add(cap.min(self.max_step))
+        }
+    }
+}
@@ -1,37 +1,64 @@
 #![allow(dead_code)]
 
 use std::{
-    alloc,
+    borrow::{Borrow, BorrowMut},
+    cmp::Ordering,
+    fmt,
+    hash::{Hash, Hasher},
+    iter::FromIterator,
+    mem,
     ops::{Deref, DerefMut},
     ptr::{self, NonNull},
+    slice,
 };
 
-pub struct Vec<T> {
-    ptr: NonNull<T>,
-    cap: usize,
+pub mod growth;
+mod raw_vec;
+
+use growth::{Doubling, GrowthPolicy};
+pub use raw_vec::RawVec;
+
+#[macro_export]
+macro_rules! vector {
+    () => {
+        $crate::vec::Vec::new()
+    };
+    ($elem:expr; $n:expr) => {
+        ::std::iter::repeat_n($elem, $n).collect::<$crate::vec::Vec<_>>()
+    };
+    ($($item:expr),+ $(,)?) => {
+        $crate::vec::Vec::from([$($item),+])
+    };
+}
+
+pub struct Vec<T, G = Doubling> {
+    buf: RawVec<T>,
     len: usize,
+    growth: G,
 }
 
 impl<T> Vec<T> {
     pub fn new() -> Vec<T> {
+        Vec::with_growth_policy(Doubling)
+    }
+}
+
+impl<T, G: GrowthPolicy> Vec<T, G> {
+    pub fn with_growth_policy(growth: G) -> Vec<T, G> {
         Vec {
-            ptr: NonNull::dangling(),
-            cap: 0,
+            buf: RawVec::new(),
             len: 0,
+            growth,
         }
     }
 
     pub fn push(&mut self, item: T) {
-        if self.len == self.cap {
-            if self.len == 0 {
-                self.grow(1);
-            } else {
-                self.grow(self.len * 2);
-            }
+        if self.len == self.capacity() {
+            self.grow_by_policy();
         }
 
         unsafe {
-            ptr::write(self.ptr.as_ptr().add(self.len), item);
+            ptr::write(self.buf.ptr().add(self.len), item);
         }
         self.len += 1;
     }
@@ -41,21 +68,21 @@ impl<T> Vec<T> {
             None
         } else {
             self.len -= 1;
-            unsafe { Some(ptr::read(self.ptr.as_ptr().add(self.len))) }
+            unsafe { Some(ptr::read(self.buf.ptr().add(self.len))) }
         }
     }
 
     pub fn insert(&mut self, index: usize, item: T) {
         assert!(index <= self.len);
 
-        if self.len == self.cap {
-            self.grow(self.len * 2);
+        if self.len == self.capacity() {
+            self.grow_by_policy();
         }
 
         unsafe {
             ptr::copy(
                 self.as_ptr().add(index),
-                self.ptr.as_ptr().add(index + 1),
+                self.buf.ptr().add(index + 1),
                 self.len - index,
             );
             ptr::write(self.as_mut_ptr().add(index), item);
@@ -70,7 +97,7 @@ impl<T> Vec<T> {
             let item = ptr::read(self.as_ptr().add(index));
             ptr::copy(
                 self.as_ptr().add(index + 1),
-                self.ptr.as_ptr().add(index),
+                self.buf.ptr().add(index),
                 self.len - index - 1,
             );
             self.len -= 1;
@@ -83,58 +110,68 @@ impl<T> Vec<T> {
     }
 
     pub fn capacity(&self) -> usize {
-        self.cap
+        self.buf.capacity()
+    }
+
+    pub fn growth_policy(&self) -> &G {
+        &self.growth
     }
 
     pub fn reserve(&mut self, len: usize) {
-        self.grow(len);
+        self.buf.grow_to(len);
     }
 
-    // TODO make this safer
-    fn grow(&mut self, new_size: usize) {
-        let old_layout = alloc::Layout::array::<T>(self.cap).unwrap();
-        let new_layout = alloc::Layout::array::<T>(new_size).unwrap();
+    pub fn shrink_to_fit(&mut self) {
+        self.shrink_to(self.len);
+    }
 
-        unsafe {
-            let ptr = if self.cap == 0 {
-                alloc::alloc(new_layout) as *mut T
-            } else {
-                alloc::realloc(self.ptr.as_ptr() as *mut u8, old_layout, new_layout.size())
-                    as *mut T
-            };
-            self.ptr = NonNull::<T>::new(ptr).unwrap();
+    pub fn shrink_to(&mut self, min_capacity: usize) {
+        self.buf.shrink_to(min_capacity.max(self.len));
+    }
+
+    // makes room for `additional` more items, growing at least as far as
+    // the policy would so a run of small reservations stays amortized
+    pub fn reserve_additional(&mut self, additional: usize) {
+        let needed = self.len.checked_add(additional).expect("capacity overflow");
+        if needed > self.capacity() {
+            let cap = self.capacity();
+            let new_cap = self.growth.next_capacity(cap).max(needed);
+            self.buf.grow_to(new_cap);
         }
+    }
 
-        self.cap = new_size;
+    fn grow_by_policy(&mut self) {
+        let cap = self.capacity();
+        let new_cap = self.growth.next_capacity(cap).max(cap + 1);
+        self.buf.grow_to(new_cap);
     }
 }
 
-impl<T> Drop for Vec<T> {
+impl<T, G> Drop for Vec<T, G> {
     fn drop(&mut self) {
-        if self.cap != 0 {
-            let layout = alloc::Layout::array::<T>(self.cap).unwrap();
-            unsafe { alloc::dealloc(self.ptr.as_ptr().cast(), layout) };
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.buf.ptr(), self.len));
         }
     }
 }
 
-impl<T> Deref for Vec<T> {
+impl<T, G> Deref for Vec<T, G> {
     type Target = [T];
     fn deref(&self) -> &[T] {
-        unsafe { std::slice::from_raw_parts(self.ptr.as_ptr(), self.len) }
+        unsafe { slice::from_raw_parts(self.buf.ptr(), self.len) }
     }
 }
 
-impl<T> DerefMut for Vec<T> {
+impl<T, G> DerefMut for Vec<T, G> {
     fn deref_mut(&mut self) -> &mut [T] {
-        unsafe { std::slice::from_raw_parts_mut(self.ptr.as_ptr(), self.len) }
+        unsafe { slice::from_raw_parts_mut(self.buf.ptr(), self.len) }
     }
 }
 
 impl<T: Clone> From<&[T]> for Vec<T> {
     fn from(slice: &[T]) -> Vec<T> {
         let mut vec = Self::new();
-        vec.grow(slice.len());
+        vec.reserve(slice.len());
         for item in slice {
             vec.push((*item).clone());
         }
@@ -145,7 +182,7 @@ impl<T: Clone> From<&[T]> for Vec<T> {
 impl<T: Clone> From<&mut [T]> for Vec<T> {
     fn from(slice: &mut [T]) -> Vec<T> {
         let mut vec = Self::new();
-        vec.grow(slice.len());
+        vec.reserve(slice.len());
         for item in slice {
             vec.push((*item).clone());
         }
@@ -153,9 +190,257 @@ impl<T: Clone> From<&mut [T]> for Vec<T> {
     }
 }
 
+impl<T, G: GrowthPolicy + Default> Default for Vec<T, G> {
+    fn default() -> Vec<T, G> {
+        Vec::with_growth_policy(G::default())
+    }
+}
+
+impl<T: Clone, G: GrowthPolicy + Clone> Clone for Vec<T, G> {
+    fn clone(&self) -> Vec<T, G> {
+        let mut vec = Vec::with_growth_policy(self.growth.clone());
+        vec.extend(self.iter().cloned());
+        vec
+    }
+}
+
+impl<T: fmt::Debug, G> fmt::Debug for Vec<T, G> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl<T: PartialEq<U>, U, G, H> PartialEq<Vec<U, H>> for Vec<T, G> {
+    fn eq(&self, other: &Vec<U, H>) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U, G> PartialEq<[U]> for Vec<T, G> {
+    fn eq(&self, other: &[U]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: PartialEq<U>, U, G, const N: usize> PartialEq<[U; N]> for Vec<T, G> {
+    fn eq(&self, other: &[U; N]) -> bool {
+        self[..] == other[..]
+    }
+}
+
+impl<T: Eq, G> Eq for Vec<T, G> {}
+
+impl<T: PartialOrd, G> PartialOrd for Vec<T, G> {
+    fn partial_cmp(&self, other: &Vec<T, G>) -> Option<Ordering> {
+        PartialOrd::partial_cmp(&**self, &**other)
+    }
+}
+
+impl<T: Ord, G> Ord for Vec<T, G> {
+    fn cmp(&self, other: &Vec<T, G>) -> Ordering {
+        Ord::cmp(&**self, &**other)
+    }
+}
+
+impl<T: Hash, G> Hash for Vec<T, G> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        Hash::hash(&**self, state)
+    }
+}
+
+impl<T, G> AsRef<[T]> for Vec<T, G> {
+    fn as_ref(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, G> AsMut<[T]> for Vec<T, G> {
+    fn as_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T, G> Borrow<[T]> for Vec<T, G> {
+    fn borrow(&self) -> &[T] {
+        self
+    }
+}
+
+impl<T, G> BorrowMut<[T]> for Vec<T, G> {
+    fn borrow_mut(&mut self) -> &mut [T] {
+        self
+    }
+}
+
+impl<T, const N: usize> From<[T; N]> for Vec<T> {
+    fn from(array: [T; N]) -> Vec<T> {
+        array.into_iter().collect()
+    }
+}
+
+impl<T> From<std::vec::Vec<T>> for Vec<T> {
+    fn from(vec: std::vec::Vec<T>) -> Vec<T> {
+        // both sides allocate with the global allocator and `Layout::array::<T>(cap)`,
+        // so the buffer can change owners without copying
+        let mut vec = mem::ManuallyDrop::new(vec);
+        Vec {
+            buf: unsafe {
+                RawVec::from_raw_parts(NonNull::new(vec.as_mut_ptr()).unwrap(), vec.capacity())
+            },
+            len: vec.len(),
+            growth: Doubling,
+        }
+    }
+}
+
+impl<T, G> From<Vec<T, G>> for std::vec::Vec<T> {
+    fn from(vec: Vec<T, G>) -> std::vec::Vec<T> {
+        let mut vec = mem::ManuallyDrop::new(vec);
+        unsafe {
+            ptr::drop_in_place(&mut vec.growth);
+            let buf = mem::ManuallyDrop::new(ptr::read(&vec.buf));
+            std::vec::Vec::from_raw_parts(buf.ptr(), vec.len, buf.capacity())
+        }
+    }
+}
+
+impl<T, G: GrowthPolicy + Default> FromIterator<T> for Vec<T, G> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Vec<T, G> {
+        let mut vec = Vec::default();
+        vec.extend(iter);
+        vec
+    }
+}
+
+impl<T, G: GrowthPolicy> Extend<T> for Vec<T, G> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        self.reserve_additional(lower);
+        for item in iter {
+            self.push(item);
+        }
+    }
+}
+
+impl<'a, T: Copy + 'a, G: GrowthPolicy> Extend<&'a T> for Vec<T, G> {
+    fn extend<I: IntoIterator<Item = &'a T>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().copied());
+    }
+}
+
+impl<T, G> IntoIterator for Vec<T, G> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        // the iterator takes over the buffer, so the vec must not drop it
+        let mut vec = mem::ManuallyDrop::new(self);
+        unsafe {
+            ptr::drop_in_place(&mut vec.growth);
+            IntoIter {
+                buf: ptr::read(&vec.buf),
+                start: 0,
+                end: vec.len,
+            }
+        }
+    }
+}
+
+impl<'a, T, G> IntoIterator for &'a Vec<T, G> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T, G> IntoIterator for &'a mut Vec<T, G> {
+    type Item = &'a mut T;
+    type IntoIter = slice::IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T> {
+    buf: RawVec<T>,
+    start: usize,
+    end: usize,
+}
+
+impl<T> IntoIter<T> {
+    pub fn as_slice(&self) -> &[T] {
+        unsafe { slice::from_raw_parts(self.buf.ptr().add(self.start), self.end - self.start) }
+    }
+}
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            let item = unsafe { ptr::read(self.buf.ptr().add(self.start)) };
+            self.start += 1;
+            Some(item)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.start;
+        (len, Some(len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.start == self.end {
+            None
+        } else {
+            self.end -= 1;
+            unsafe { Some(ptr::read(self.buf.ptr().add(self.end))) }
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> Drop for IntoIter<T> {
+    fn drop(&mut self) {
+        unsafe {
+            ptr::drop_in_place(ptr::slice_from_raw_parts_mut(
+                self.buf.ptr().add(self.start),
+                self.end - self.start,
+            ));
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Vec;
+    use std::{
+        cell::Cell,
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+        rc::Rc,
+    };
+
+    use super::{
+        growth::{Capped, FixedIncrement, GrowthPolicy, OneAndHalf},
+        Vec,
+    };
+
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
 
     #[test]
     fn push_pop_one() {
@@ -211,4 +496,213 @@ mod tests {
         assert_eq!(vec[2], 4);
         assert_eq!(vec[3], 5);
     }
+
+    #[test]
+    fn drop_items() {
+        let drops = Rc::new(Cell::new(0));
+        let mut vec = Vec::new();
+        (0..5).for_each(|_| vec.push(DropCounter(drops.clone())));
+        drop(vec.pop());
+        assert_eq!(drops.get(), 1);
+        drop(vec);
+        assert_eq!(drops.get(), 5);
+    }
+
+    #[test]
+    fn into_iter() {
+        let vec: Vec<String> = ["a", "b", "c", "d"].iter().map(|s| s.to_string()).collect();
+        let mut iter = vec.into_iter();
+        assert_eq!(iter.len(), 4);
+        assert_eq!(iter.next().as_deref(), Some("a"));
+        assert_eq!(iter.next_back().as_deref(), Some("d"));
+        assert_eq!(iter.as_slice(), ["b", "c"]);
+        assert_eq!(iter.collect::<std::vec::Vec<_>>(), ["b", "c"]);
+    }
+
+    #[test]
+    fn into_iter_drops_remaining() {
+        let drops = Rc::new(Cell::new(0));
+        let vec: Vec<_> = (0..4).map(|_| DropCounter(drops.clone())).collect();
+        let mut iter = vec.into_iter();
+        drop(iter.next());
+        assert_eq!(drops.get(), 1);
+        drop(iter);
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    fn iter_by_ref() {
+        let mut vec: Vec<i32> = (1..=3).collect();
+        for item in &mut vec {
+            *item *= 2;
+        }
+        assert_eq!((&vec).into_iter().sum::<i32>(), 12);
+    }
+
+    #[test]
+    fn from_iter_preallocates() {
+        let vec: Vec<u32> = (0..10).collect();
+        assert_eq!(vec.len(), 10);
+        assert_eq!(vec.capacity(), 10);
+    }
+
+    #[test]
+    fn extend() {
+        let mut vec: Vec<u32> = (0..3).collect();
+        vec.extend([3, 4, 5].iter());
+        vec.extend(6..8);
+        assert_eq!(vec.capacity(), 12);
+        assert_eq!(vec[..], [0, 1, 2, 3, 4, 5, 6, 7]);
+
+        // one item at a time still grows by the policy, not to the exact length
+        let mut vec: Vec<u32> = Vec::new();
+        for n in 0..100 {
+            vec.extend([n]);
+            assert!(vec.capacity().is_power_of_two());
+        }
+        assert!(vec.iter().copied().eq(0..100));
+    }
+
+    #[test]
+    fn clone_and_compare() {
+        let vec: Vec<String> = ["a", "b"].iter().map(|s| s.to_string()).collect();
+        let other = vec.clone();
+        assert_eq!(vec, other);
+        assert_eq!(format!("{:?}", other), r#"["a", "b"]"#);
+        let (short, long, larger) = (Vec::from([1, 2]), Vec::from([1, 2, 3]), Vec::from([1, 3]));
+        assert!(short < long);
+        assert!(long < larger);
+        assert_eq!(Vec::<u8>::default().len(), 0);
+    }
+
+    #[test]
+    fn hash_matches_slice() {
+        let hash = |item: &dyn Fn(&mut DefaultHasher)| {
+            let mut hasher = DefaultHasher::new();
+            item(&mut hasher);
+            hasher.finish()
+        };
+        let vec = Vec::from([1, 2, 3]);
+        assert_eq!(
+            hash(&|h| vec.hash(h)),
+            hash(&|h| [1, 2, 3].as_slice().hash(h))
+        );
+    }
+
+    #[test]
+    fn std_vec_round_trip() {
+        let std_vec = vec![String::from("a"), String::from("b")];
+        let ptr = std_vec.as_ptr();
+        let vec = Vec::from(std_vec);
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(vec, ["a", "b"]);
+
+        let std_vec: std::vec::Vec<String> = vec.into();
+        assert_eq!(std_vec.as_ptr(), ptr);
+
+        let zsts: Vec<()> = Vec::from(vec![(); 3]);
+        assert_eq!(zsts.len(), 3);
+        let zsts: std::vec::Vec<()> = zsts.into();
+        assert_eq!(zsts.len(), 3);
+    }
+
+    #[test]
+    fn vector_macro() {
+        let empty: Vec<u8> = crate::vector![];
+        assert_eq!(empty.len(), 0);
+        assert_eq!(crate::vector![1, 2, 3], [1, 2, 3]);
+        assert_eq!(crate::vector![String::from("x"); 2], ["x", "x"]);
+    }
+
+    fn capacities<G: GrowthPolicy>(mut vec: Vec<u32, G>, pushes: u32) -> std::vec::Vec<usize> {
+        let mut caps = std::vec::Vec::new();
+        for n in 0..pushes {
+            vec.push(n);
+            if caps.last() != Some(&vec.capacity()) {
+                caps.push(vec.capacity());
+            }
+        }
+        caps
+    }
+
+    #[test]
+    fn growth_doubling() {
+        assert_eq!(capacities(Vec::new(), 17), [1, 2, 4, 8, 16, 32]);
+    }
+
+    #[test]
+    fn growth_one_and_half() {
+        let vec = Vec::with_growth_policy(OneAndHalf);
+        assert_eq!(capacities(vec, 20), [1, 2, 3, 4, 6, 9, 13, 19, 28]);
+    }
+
+    #[test]
+    fn growth_fixed_increment() {
+        let vec = Vec::with_growth_policy(FixedIncrement(4));
+        assert_eq!(capacities(vec, 13), [4, 8, 12, 16]);
+
+        let vec = Vec::with_growth_policy(FixedIncrement(0));
+        assert_eq!(capacities(vec, 3), [1, 2, 3]);
+    }
+
+    #[test]
+    fn growth_capped() {
+        let vec = Vec::with_growth_policy(Capped {
+            threshold: 8,
+            max_step: 5,
+        });
+        assert_eq!(capacities(vec, 30), [1, 2, 4, 8, 13, 18, 23, 28, 33]);
+    }
+
+    #[test]
+    fn growth_on_insert() {
+        let mut vec = Vec::with_growth_policy(FixedIncrement(3));
+        vec.insert(0, 1);
+        assert_eq!(vec.capacity(), 3);
+        vec.insert(0, 2);
+        vec.insert(0, 3);
+        vec.insert(0, 4);
+        assert_eq!(vec.capacity(), 6);
+        assert_eq!(vec, [4, 3, 2, 1]);
+    }
+
+    #[test]
+    fn shrink() {
+        let mut vec: Vec<String> = (0..5).map(|n| n.to_string()).collect();
+        vec.reserve(32);
+        assert_eq!(vec.capacity(), 32);
+
+        vec.shrink_to(10);
+        assert_eq!(vec.capacity(), 10);
+        vec.shrink_to(2);
+        assert_eq!(vec.capacity(), 5);
+        vec.shrink_to(20);
+        assert_eq!(vec.capacity(), 5);
+        assert_eq!(vec, ["0", "1", "2", "3", "4"]);
+
+        while vec.pop().is_some() {}
+        vec.shrink_to_fit();
+        assert_eq!(vec.capacity(), 0);
+        vec.push(String::from("a"));
+        assert_eq!(vec, ["a"]);
+    }
+
+    #[test]
+    fn zero_sized() {
+        let mut vec = Vec::new();
+        assert_eq!(vec.capacity(), usize::MAX);
+        (0..10).for_each(|_| vec.push(()));
+        vec.insert(3, ());
+        assert_eq!(vec.len(), 11);
+        assert_eq!(vec.pop(), Some(()));
+        assert_eq!(vec.into_iter().count(), 10);
+    }
+
+    #[test]
+    fn empty_clone() {
+        let vec: Vec<String> = Vec::new();
+        let other = vec.clone();
+        assert_eq!(other.capacity(), 0);
+        assert_eq!(Vec::from([0u8; 0].as_slice()).capacity(), 0);
+    }
 }
@@ -0,0 +1,104 @@
+use std::{alloc, mem, ptr::NonNull};
+
+// Owns an allocation for `cap` items without tracking which of them are
+// initialized, that's left to the containers built on top of it.
+// Zero-sized types never allocate and report a capacity of `usize::MAX`.
+pub struct RawVec<T> {
+    ptr: NonNull<T>,
+    cap: usize,
+}
+
+unsafe impl<T: Send> Send for RawVec<T> {}
+unsafe impl<T: Sync> Sync for RawVec<T> {}
+
+impl<T> RawVec<T> {
+    const IS_ZST: bool = mem::size_of::<T>() == 0;
+
+    pub fn new() -> RawVec<T> {
+        RawVec {
+            ptr: NonNull::dangling(),
+            cap: if Self::IS_ZST { usize::MAX } else { 0 },
+        }
+    }
+
+    pub fn with_capacity(cap: usize) -> RawVec<T> {
+        let mut buf = RawVec::new();
+        buf.grow_to(cap);
+        buf
+    }
+
+    // `ptr` and `cap` must describe an allocation made by the global allocator
+    // with `Layout::array::<T>(cap)`, or `cap` must be 0
+    pub unsafe fn from_raw_parts(ptr: NonNull<T>, cap: usize) -> RawVec<T> {
+        RawVec {
+            ptr,
+            cap: if Self::IS_ZST { usize::MAX } else { cap },
+        }
+    }
+
+    pub fn ptr(&self) -> *mut T {
+        self.ptr.as_ptr()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.cap
+    }
+
+    pub fn grow_to(&mut self, new_cap: usize) {
+        if Self::IS_ZST {
+            assert!(new_cap <= self.cap, "capacity overflow");
+            return;
+        }
+        if new_cap <= self.cap {
+            return;
+        }
+
+        let new_layout = alloc::Layout::array::<T>(new_cap).expect("capacity overflow");
+        unsafe {
+            let ptr = if self.cap == 0 {
+                alloc::alloc(new_layout)
+            } else {
+                alloc::realloc(self.ptr.as_ptr().cast(), self.layout(), new_layout.size())
+            };
+            self.ptr = match NonNull::new(ptr as *mut T) {
+                Some(ptr) => ptr,
+                None => alloc::handle_alloc_error(new_layout),
+            };
+        }
+        self.cap = new_cap;
+    }
+
+    pub fn shrink_to(&mut self, new_cap: usize) {
+        if Self::IS_ZST || new_cap >= self.cap {
+            return;
+        }
+
+        unsafe {
+            if new_cap == 0 {
+                alloc::dealloc(self.ptr.as_ptr().cast(), self.layout());
+                self.ptr = NonNull::dangling();
+            } else {
+                let new_layout = alloc::Layout::array::<T>(new_cap).unwrap();
+                let ptr =
+                    alloc::realloc(self.ptr.as_ptr().cast(), self.layout(), new_layout.size());
+                self.ptr = match NonNull::new(ptr as *mut T) {
+                    Some(ptr) => ptr,
+                    None => alloc::handle_alloc_error(new_layout),
+                };
+            }
+        }
+        self.cap = new_cap;
+    }
+
+    fn layout(&self) -> alloc::Layout {
+        alloc::Layout::array::<T>(self.cap).unwrap()
+    }
+}
+
+impl<T> Drop for RawVec<T> {
+    fn drop(&mut self) {
+        if !Self::IS_ZST && self.cap != 0 {
+            unsafe { alloc::dealloc(self.ptr.as_ptr().cast(), self.layout()) };
+        }
+    }
+}
@@ -0,0 +1,472 @@
+#![allow(dead_code)]
+
+use std::{
+    fmt,
+    iter::{Chain, FromIterator},
+    mem::MaybeUninit,
+    ops::{Index, IndexMut},
+    ptr, slice,
+};
+
+use crate::vec::{
+    growth::{Doubling, GrowthPolicy},
+    RawVec,
+};
+
+// Items live in `buf` starting at the physical index `head` and wrap around
+// to the start of the buffer once they reach its end.
+pub struct VecDeque<T> {
+    buf: RawVec<T>,
+    head: usize,
+    len: usize,
+}
+
+impl<T> VecDeque<T> {
+    pub fn new() -> VecDeque<T> {
+        VecDeque {
+            buf: RawVec::new(),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn with_capacity(cap: usize) -> VecDeque<T> {
+        VecDeque {
+            buf: RawVec::with_capacity(cap),
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.buf.capacity()
+    }
+
+    pub fn push_back(&mut self, item: T) {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+
+        unsafe { ptr::write(self.slot(self.len), item) };
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, item: T) {
+        if self.len == self.capacity() {
+            self.grow();
+        }
+
+        self.head = self.wrap_sub(self.head, 1);
+        unsafe { ptr::write(self.buf.ptr().add(self.head), item) };
+        self.len += 1;
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            self.len -= 1;
+            unsafe { Some(ptr::read(self.slot(self.len))) }
+        }
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            None
+        } else {
+            let item = unsafe { ptr::read(self.buf.ptr().add(self.head)) };
+            self.head = self.to_physical(1);
+            self.len -= 1;
+            Some(item)
+        }
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index < self.len {
+            unsafe { Some(&*self.slot(index)) }
+        } else {
+            None
+        }
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len {
+            unsafe { Some(&mut *self.slot(index)) }
+        } else {
+            None
+        }
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.get(0)
+    }
+
+    pub fn back(&self) -> Option<&T> {
+        self.len.checked_sub(1).and_then(|index| self.get(index))
+    }
+
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let (head_len, tail_len) = self.segment_lens();
+        unsafe {
+            (
+                slice::from_raw_parts(self.buf.ptr().add(self.head), head_len),
+                slice::from_raw_parts(self.buf.ptr(), tail_len),
+            )
+        }
+    }
+
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let (head_len, tail_len) = self.segment_lens();
+        unsafe {
+            (
+                slice::from_raw_parts_mut(self.buf.ptr().add(self.head), head_len),
+                slice::from_raw_parts_mut(self.buf.ptr(), tail_len),
+            )
+        }
+    }
+
+    pub fn make_contiguous(&mut self) -> &mut [T] {
+        let (_, tail_len) = self.segment_lens();
+        if tail_len != 0 {
+            // rotating the whole buffer moves uninitialized slots around too,
+            // so it has to be done on `MaybeUninit<T>`
+            unsafe {
+                let buf = slice::from_raw_parts_mut(
+                    self.buf.ptr() as *mut MaybeUninit<T>,
+                    self.capacity(),
+                );
+                buf.rotate_left(self.head);
+            }
+            self.head = 0;
+        }
+        self.as_mut_slices().0
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        let (head, tail) = self.as_slices();
+        head.iter().chain(tail.iter())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        let (head, tail) = self.as_mut_slices();
+        head.iter_mut().chain(tail.iter_mut())
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_back().is_some() {}
+        self.head = 0;
+    }
+
+    fn grow(&mut self) {
+        let old_cap = self.capacity();
+        let new_cap = Doubling.next_capacity(old_cap).max(old_cap + 1);
+        self.buf.grow_to(new_cap);
+
+        if self.head + self.len <= old_cap {
+            // [ . . H x x x x T . . ] nothing wrapped around, nothing to move
+            return;
+        }
+
+        let head_len = old_cap - self.head;
+        let tail_len = self.len - head_len;
+        if tail_len < head_len && tail_len <= new_cap - old_cap {
+            // [ x x T . . H x x x x | . . . . . . ] -> [ . . . . . H x x x x | x x T . . . ]
+            unsafe {
+                ptr::copy_nonoverlapping(self.buf.ptr(), self.buf.ptr().add(old_cap), tail_len)
+            };
+        } else {
+            // [ x x x x T . . H x x | . . . . . . ] -> [ x x x x T . . . . . | . . . . H x x ]
+            let new_head = new_cap - head_len;
+            unsafe {
+                ptr::copy(
+                    self.buf.ptr().add(self.head),
+                    self.buf.ptr().add(new_head),
+                    head_len,
+                )
+            };
+            self.head = new_head;
+        }
+    }
+
+    fn segment_lens(&self) -> (usize, usize) {
+        let head_room = self.capacity() - self.head;
+        if self.len <= head_room {
+            (self.len, 0)
+        } else {
+            (head_room, self.len - head_room)
+        }
+    }
+
+    fn slot(&self, index: usize) -> *mut T {
+        unsafe { self.buf.ptr().add(self.to_physical(index)) }
+    }
+
+    fn to_physical(&self, index: usize) -> usize {
+        self.wrap_add(self.head, index)
+    }
+
+    fn wrap_add(&self, index: usize, offset: usize) -> usize {
+        let room = self.capacity() - index;
+        if offset >= room {
+            offset - room
+        } else {
+            index + offset
+        }
+    }
+
+    fn wrap_sub(&self, index: usize, offset: usize) -> usize {
+        if offset > index {
+            self.capacity() - (offset - index)
+        } else {
+            index - offset
+        }
+    }
+}
+
+impl<T> Drop for VecDeque<T> {
+    fn drop(&mut self) {
+        let (head, tail) = self.as_mut_slices();
+        unsafe {
+            ptr::drop_in_place(head);
+            ptr::drop_in_place(tail);
+        }
+    }
+}
+
+impl<T> Default for VecDeque<T> {
+    fn default() -> VecDeque<T> {
+        VecDeque::new()
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for VecDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Index<usize> for VecDeque<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &T {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for VecDeque<T> {
+    fn index_mut(&mut self, index: usize) -> &mut T {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<T> FromIterator<T> for VecDeque<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> VecDeque<T> {
+        let mut deque = VecDeque::new();
+        deque.extend(iter);
+        deque
+    }
+}
+
+impl<T> Extend<T> for VecDeque<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        let (lower, _) = iter.size_hint();
+        let needed = self.len.checked_add(lower).expect("capacity overflow");
+        if needed > self.capacity() {
+            // grow at least as far as `grow` would, so repeated extends stay amortized
+            let cap = self.capacity();
+            self.make_contiguous();
+            self.buf.grow_to(Doubling.next_capacity(cap).max(needed));
+        }
+        for item in iter {
+            self.push_back(item);
+        }
+    }
+}
+
+pub type Iter<'a, T> = Chain<slice::Iter<'a, T>, slice::Iter<'a, T>>;
+pub type IterMut<'a, T> = Chain<slice::IterMut<'a, T>, slice::IterMut<'a, T>>;
+
+pub struct IntoIter<T>(VecDeque<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for VecDeque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a VecDeque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut VecDeque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::VecDeque;
+
+    struct DropCounter(Rc<Cell<usize>>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut deque = VecDeque::new();
+        deque.push_back(2);
+        deque.push_back(3);
+        deque.push_front(1);
+        deque.push_front(0);
+        assert_eq!(deque.len(), 4);
+        assert_eq!(deque.front(), Some(&0));
+        assert_eq!(deque.back(), Some(&3));
+        assert_eq!(deque.pop_front(), Some(0));
+        assert_eq!(deque.pop_back(), Some(3));
+        assert_eq!(deque.pop_front(), Some(1));
+        assert_eq!(deque.pop_front(), Some(2));
+        assert_eq!(deque.pop_front(), None);
+        assert_eq!(deque.pop_back(), None);
+    }
+
+    #[test]
+    fn index() {
+        let mut deque: VecDeque<i32> = (0..4).collect();
+        deque.push_front(-1);
+        assert_eq!(deque[0], -1);
+        assert_eq!(deque[4], 3);
+        deque[4] = 10;
+        assert_eq!(deque.get(4), Some(&10));
+        assert_eq!(deque.get(5), None);
+    }
+
+    #[test]
+    fn wrapped_slices() {
+        let mut deque = VecDeque::with_capacity(4);
+        deque.push_back(2);
+        deque.push_back(3);
+        deque.push_front(1);
+        assert_eq!(deque.as_slices(), (&[1][..], &[2, 3][..]));
+        assert_eq!(deque.make_contiguous(), [1, 2, 3]);
+        assert_eq!(deque.as_slices(), (&[1, 2, 3][..], &[][..]));
+    }
+
+    #[test]
+    fn grow_moves_short_tail() {
+        let mut deque = VecDeque::with_capacity(4);
+        (0..4).for_each(|n| deque.push_back(n));
+        deque.pop_front();
+        deque.push_back(4);
+        assert_eq!(deque.as_slices(), (&[1, 2, 3][..], &[4][..]));
+        deque.push_back(5);
+        assert_eq!(deque.capacity(), 8);
+        assert_eq!(deque.as_slices(), (&[1, 2, 3, 4, 5][..], &[][..]));
+    }
+
+    #[test]
+    fn grow_moves_short_head() {
+        let mut deque = VecDeque::with_capacity(4);
+        (1..4).for_each(|n| deque.push_back(n));
+        deque.push_front(0);
+        assert_eq!(deque.as_slices(), (&[0][..], &[1, 2, 3][..]));
+        deque.push_front(-1);
+        assert_eq!(deque.capacity(), 8);
+        assert_eq!(deque.as_slices(), (&[-1, 0][..], &[1, 2, 3][..]));
+        assert_eq!(deque.into_iter().collect::<Vec<_>>(), [-1, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn iter_mut() {
+        let mut deque: VecDeque<i32> = (0..3).collect();
+        deque.push_front(-1);
+        for item in &mut deque {
+            *item *= 2;
+        }
+        assert_eq!(
+            (&deque).into_iter().copied().collect::<Vec<_>>(),
+            [-2, 0, 2, 4]
+        );
+        assert_eq!(format!("{:?}", deque), "[-2, 0, 2, 4]");
+    }
+
+    #[test]
+    fn extend_grows_by_doubling() {
+        let mut deque: VecDeque<i32> = (0..3).collect();
+        assert_eq!(deque.capacity(), 3);
+        deque.extend([3]);
+        assert_eq!(deque.capacity(), 6);
+        deque.extend(4..20);
+        assert_eq!(deque.capacity(), 20);
+        assert!(deque.iter().copied().eq(0..20));
+    }
+
+    #[test]
+    fn drop_items() {
+        let drops = Rc::new(Cell::new(0));
+        let mut deque = VecDeque::with_capacity(4);
+        (0..3).for_each(|_| deque.push_back(DropCounter(drops.clone())));
+        deque.push_front(DropCounter(drops.clone()));
+        drop(deque.pop_back());
+        assert_eq!(drops.get(), 1);
+        drop(deque);
+        assert_eq!(drops.get(), 4);
+    }
+
+    #[test]
+    fn zero_sized() {
+        let mut deque = VecDeque::new();
+        deque.push_front(());
+        deque.push_back(());
+        assert_eq!(deque.len(), 2);
+        assert_eq!(deque.pop_front(), Some(()));
+        assert_eq!(deque.pop_back(), Some(()));
+        assert_eq!(deque.pop_back(), None);
+    }
+}